@@ -1,11 +1,5 @@
-// [[file:../../xo-tools.note::572df32d][572df32d]]
-use gut::prelude::*;
-// 572df32d ends here
-
 // [[file:../../xo-tools.note::2814494e][2814494e]]
-fn main() -> Result<()> {
-    gaussian_tools::cli::restart::enter_main()?;
-
-    Ok(())
+fn main() -> std::process::ExitCode {
+    gaussian_tools::error::report(gaussian_tools::cli::restart::enter_main())
 }
 // 2814494e ends here
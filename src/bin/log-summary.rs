@@ -1,11 +1,5 @@
-// [[file:../../xo-tools.note::*imports][imports:1]]
-use gut::prelude::*;
-// imports:1 ends here
-
 // [[file:../../xo-tools.note::dfe39253][dfe39253]]
-fn main() -> Result<()> {
-    gaussian_tools::cli::log::enter_main()?;
-
-    Ok(())
+fn main() -> std::process::ExitCode {
+    gaussian_tools::error::report(gaussian_tools::cli::log::enter_main())
 }
 // dfe39253 ends here
@@ -1,10 +1,5 @@
-// [[file:../../xo-tools.note::*imports][imports:1]]
-use gut::prelude::*;
-// imports:1 ends here
-
 // [[file:../../xo-tools.note::c41a5bad][c41a5bad]]
-fn main() -> Result<()> {
-    gaussian_tools::cli::cubegen::enter_main()?;
-    Ok(())
+fn main() -> std::process::ExitCode {
+    gaussian_tools::error::report(gaussian_tools::cli::cubegen::enter_main())
 }
 // c41a5bad ends here
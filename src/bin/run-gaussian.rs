@@ -1,11 +1,5 @@
-// [[file:../../xo-tools.note::bca0dc42][bca0dc42]]
-use gut::prelude::*;
-// bca0dc42 ends here
-
 // [[file:../../xo-tools.note::3f24c131][3f24c131]]
-fn main() -> Result<()> {
-    gaussian_tools::cli::run::enter_main()?;
-
-    Ok(())
+fn main() -> std::process::ExitCode {
+    gaussian_tools::error::report(gaussian_tools::cli::run::enter_main())
 }
 // 3f24c131 ends here
@@ -1,131 +1,23 @@
 // [[file:../../xo-tools.note::*imports][imports:1]]
 use super::*;
+use crate::logfile::LogSummary;
+use clap::ValueEnum;
+use std::io::IsTerminal;
 // imports:1 ends here
 
-// [[file:../../xo-tools.note::1c08ebe1][1c08ebe1]]
-macro_rules! banner {
-    () => {
-        println!(" {:-^72}", "");
-    };
-}
-
-macro_rules! print_next_line {
-    ($lines:ident) => {
-        let line = $lines.next();
-        if line.is_none() {
-            break;
-        }
-        let line = line.unwrap()?;
-        println!("{}", line);
-    };
-}
-
-/// Walk the flog and print the essential information.
-fn summarize_gauss_log<R: BufRead>(flog: R) -> Result<()> {
-    let mut lines = flog.lines();
-
-    let mut first_time = true;
-    while let Some(line) = lines.next() {
-        let line = line?;
-        if line.contains("Revision") {
-            debug!("{}", line);
-        } else if line.starts_with(" Stoichiometry") {
-            debug!("{}", line);
-        } else if line.starts_with(" Standard basis:") {
-            debug!("{}", line);
-        } else if line.starts_with(" General basis") {
-            debug!("{}", line);
-        } else if line.starts_with(" Framework group") {
-            debug!("{}", line);
-        } else if line.starts_with(" Deg. of freedom") {
-            info!("{}", line);
-            banner!();
-        } else if line.contains("Standard basis") {
-            debug!("{}", line);
-        } else if line.contains("basis functions") {
-            debug!("{}", line);
-        } else if line.contains("(Enter ") {
-            debug!("{}", line);
-        } else if line.contains("Leave Link ") {
-            debug!("{}", line);
-        } else if line.contains("Number of steps in this run=") {
-            info!("{}", line);
-        } else if line.contains("Convergence criterion not met") {
-            info!("{}", line);
-            // # print SCF information and the next two lines
-        } else if line.starts_with(" SCF Done: ") {
-            warn!("{line}");
-            print_next_line!(lines);
-            banner!();
-        } else if line.contains("Step number") {
-            info!("{}", line);
-        } else if line.contains("exceeded") {
-            info!("{}", line);
-        } else if line.contains("energy=") {
-            debug!("{}", line);
-        } else if line.contains("Counterpoise:") {
-            info!("{}", line);
-        } else if line.starts_with(" Energy=") {
-            info!("{}", line);
-        } else if line.starts_with(" Cycle ") {
-            trace!("{}", line);
-        } else if line.starts_with(" E=") {
-            trace!("{}", line);
-        } else if line.contains("ONIOM: generating point") {
-            info!("{}", line);
-        } else if line.contains("ONIOM: extrapolated energy") {
-            info!("{}", line);
-        } else if line.contains("ONIOM: Dipole moment") {
-            info!("{}", line);
-            print_next_line!(lines);
-        } else if line.contains("Eigenvalues ---") {
-            info!("{}", line);
-            // skip other Eigenvalues lines
-            while let Some(line) = lines.next() {
-                let line = line?;
-                if !line.contains("Eigenvalues ---") {
-                    break;
-                }
-            }
-        }
-        // # print converged information
-        else if line.contains("Converged?") {
-            banner!();
-            info!("{}", line);
-            for _ in 0..7 {
-                print_next_line!(lines);
-            }
-            banner!();
-        } else if line.contains("WARNING") {
-            warn!("{}", line);
-        } else if line.contains("Warning") {
-            warn!("{}", line);
-        } else if line.contains("Frequencies --") {
-            if first_time {
-                info!("{}", line);
-            }
-            first_time = false;
-        } else if line.contains("Zero-point correction=") {
-            info!("{}", line);
-        } else if line.contains("Thermal correction to") {
-            info!("{}", line);
-        } else if line.contains("Sum of electronic and") {
-            info!("{}", line);
-            if line.contains("thermal Free Energies") {
-                banner!();
-            }
-        } else if line.contains("termination") {
-            info!("{}", line);
-        } else if line.contains("Job cpu time:") {
-            info!("{}", line);
-        }
-    }
-
-    Ok(())
+// [[file:../../xo-tools.note::6df1e54a][6df1e54a]]
+/// Output format for log summary
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// Human-readable text in a pager
+    Text,
+    /// JSON for scripts
+    Json,
+    /// Comma-separated values of SCF energies in all steps, or the
+    /// convergence table with `--convergence`
+    Csv,
 }
-// 1c08ebe1 ends here
 
-// [[file:../../xo-tools.note::6df1e54a][6df1e54a]]
 /// Print important lines found in a Gaussian output file.
 #[derive(Debug, StructOpt)]
 #[clap(author, version, about)]
@@ -137,6 +29,32 @@ struct Cli {
     #[structopt(long = "all", short = 'a')]
     show_all: bool,
 
+    /// Print the convergence table of geometry optimization steps only.
+    #[clap(long)]
+    convergence: bool,
+
+    /// Draw SCF energy and max force of geometry optimization steps in
+    /// sparklines only. Plain ASCII characters are used if stdout is not a
+    /// terminal.
+    #[clap(long, conflicts_with = "convergence")]
+    chart: bool,
+
+    /// Follow the log of a running Gaussian job while being written,
+    /// showing current step, SCF cycle and convergence, until Gaussian
+    /// terminates. Exit with error if Gaussian terminates abnormally.
+    #[clap(long, conflicts_with_all = ["chart", "convergence"])]
+    follow: bool,
+
+    /// Stop following if the log is not written for this many seconds, as
+    /// for a job killed by scheduler without termination. 0 to wait
+    /// forever.
+    #[clap(long, default_value = "3600", requires = "follow")]
+    timeout: u64,
+
+    /// Output format.
+    #[clap(long, value_enum, default_value = "text")]
+    format: Format,
+
     /// Path to Gaussian log file to be summarized.
     log_file: PathBuf,
 }
@@ -145,11 +63,49 @@ pub fn enter_main() -> Result<()> {
     let args = Cli::parse();
     gut::logger::setup_plain_logger();
 
-    // setup a pager like `less` cmd
-    pager::Pager::with_pager("less").setup();
+    if args.follow {
+        let mut last = String::new();
+        let f = &args.log_file;
+        let interval = std::time::Duration::from_secs(2);
+        let timeout = (args.timeout > 0).then(|| std::time::Duration::from_secs(args.timeout));
+        let summary = LogSummary::follow(f, interval, timeout, |parser| {
+            let status = parser.status();
+            if status != last {
+                eprintln!("{status}");
+                last = status;
+            }
+        })?;
+        match args.format {
+            Format::Text => print!("{}", summary.to_text()),
+            Format::Json => println!("{}", summary.to_json()?),
+            Format::Csv => print!("{}", summary.to_csv()),
+        }
+        return summary.check_termination(f);
+    }
 
-    let reader = file_reader(args.log_file)?;
-    let _ = summarize_gauss_log(reader)?;
+    let summary = LogSummary::from_file(&args.log_file)?;
+    if args.chart {
+        let unicode = std::io::stdout().is_terminal();
+        print!("{}", summary.optimization_chart(unicode));
+        return Ok(());
+    }
+    if args.convergence {
+        match args.format {
+            Format::Text => print!("{}", summary.optimization_text()),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&summary.optimization_steps())?),
+            Format::Csv => print!("{}", summary.optimization_csv()),
+        }
+        return Ok(());
+    }
+    match args.format {
+        Format::Text => {
+            // setup a pager like `less` cmd
+            pager::Pager::with_pager("less").setup();
+            print!("{}", summary.to_text());
+        }
+        Format::Json => println!("{}", summary.to_json()?),
+        Format::Csv => print!("{}", summary.to_csv()),
+    }
 
     Ok(())
 }
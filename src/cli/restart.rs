@@ -1,148 +1,82 @@
 // [[file:../../xo-tools.note::3ae69dc7][3ae69dc7]]
 use super::*;
+use crate::error::GaussianError;
+use crate::input::GaussianInput;
+use crate::logfile::parse_gaussian_log_file;
 // 3ae69dc7 ends here
 
-// [[file:../../xo-tools.note::*log][log:1]]
-/// Parse xyz coordinates from gaussian log file.
-fn parse_gaussian_log_file<P: AsRef<Path>>(path: P) -> Result<Vec<[f64; 3]>> {
-    let reader = file_reader(&path)?;
-
-    // the keyword indicating lines for coordinates information
-    let key = " orientation:";
-
-    // record line number containing `orientation` line.
-    let mut line_numbers = vec![];
-
-    // record number of total atoms
-    let mut natoms = None;
-
-    // record current line number
-    let mut nline = 0;
-    for line in reader.lines() {
-        let line = line?;
-        if natoms.is_none() {
-            // Sample line:
-            // ------------
-            //  NAtoms=     60 NQM=       60 NQMF=       0 NMMI=      0 NMMIF=      0
-            //
-            let k = " NAtoms=";
-            if line.starts_with(k) {
-                let parts: Vec<_> = line[k.len()..].trim_start().splitn(2, " ").collect();
-                let n: usize = parts[0].parse()?;
-                natoms = Some(n);
-            }
-        }
-
-        if line.contains(key) {
-            line_numbers.push(nline);
-        }
-        nline += 1;
-    }
-
-    // save the total number of lines.
-    let nlines = nline;
-
-    // take the last good record of coordinates during multiple-step optimization.
-    assert!(
-        line_numbers.len() >= 1,
-        "incomplete coordinates records: {:?}",
-        line_numbers
-    );
-
-    let natoms = natoms.expect("Failed to get total number of atoms.");
-    let nstart = match line_numbers.len() {
-        0 => panic!("incomplete coordinates records: {:?}", line_numbers),
-        1 => line_numbers[0],
-        _ => {
-            let x = line_numbers.pop().unwrap();
-            if natoms + x < nlines {
-                line_numbers.pop().unwrap()
-            } else {
-                x
-            }
-        }
-    };
-
-    info!("Start line number of coordinates: {}", nstart);
-    let reader = file_reader(&path)?;
-
-    // Sample record
-    //
-    // Standard orientation:
-    // ---------------------------------------------------------------------
-    //     Center     Atomic      Atomic             Coordinates (Angstroms)
-    //     Number     Number       Type             X           Y           Z
-    //     ---------------------------------------------------------------------
-    //     1          6           0        1.196105    0.388638    3.377408
-    //     2          6           0        0.739233   -1.017467    3.377408
-
-    let mut coords: Vec<_> = vec![];
-    for (line, _) in reader.lines().skip(nstart + 5).zip(0..natoms) {
-        let line = line?;
-        let parts: Vec<_> = line.split_whitespace().collect();
-        let x: f64 = parts[3].parse()?;
-        let y: f64 = parts[4].parse()?;
-        let z: f64 = parts[5].parse()?;
-        coords.push([x, y, z]);
-    }
-    assert_eq!(coords.len(), natoms);
-
-    Ok(coords)
-}
-
-#[test]
-#[ignore]
-fn test_parse_log() -> Result<()> {
-    let fname = "/share/apps/gaussian/g09/tests/amd64/test0333.log";
-    let coords = parse_gaussian_log_file(fname)?;
-
-    Ok(())
-}
-// log:1 ends here
-
 // [[file:../../xo-tools.note::6c25fcb8][6c25fcb8]]
 /// Update input with new coordinates
 ///
 /// # Parameters
 /// * coords: xyz coordinates from Gaussian log file.
 /// * path: path to Gaussian input file, *.com, *.gjf
+/// * keep_includes: keep include lines like "@basis.gbs /N" instead of inlining them.
 ///
-fn update_with_coordinates<P: AsRef<Path>>(path: P, coords: &[[f64; 3]]) -> Result<String> {
-    assert!(!coords.is_empty(), "no coords");
+fn update_with_coordinates<P: AsRef<Path>>(path: P, coords: &[[f64; 3]], keep_includes: bool) -> Result<String> {
+    if coords.is_empty() {
+        bail!("no coords");
+    }
     let path = path.as_ref();
     info!("update file {path:?} with new coords");
     let pat = r"\s+[-0-9]+\.[0-9]+\s+[-0-9]+\.[0-9]+\s+[-0-9]+\.[0-9]+";
     let re = regex::Regex::new(pat).unwrap();
 
-    let mut lines = vec![];
-    let mut coords_iter = coords.iter();
-    let reader = file_reader(path)?;
-    let mut ireplaced = 0;
-    for line in reader.lines() {
-        let line = line?;
-        let line = if let Some(m) = re.find(&line) {
-            let s = m.start();
-            let e = m.end();
-            let xyz_old = &line[s..e];
-            if let Some([x, y, z]) = coords_iter.next() {
-                let xyz_new = format!("{:20.8}{:20.8}{:20.8}", x, y, z);
-                ireplaced += 1;
-                line.replace(xyz_old, &xyz_new)
-            } else {
-                panic!("output coords is inconsistent with input structure.");
-            }
-        } else {
-            line
+    let s = crate::input::read_input_file(path, keep_includes)?;
+    let mut input: GaussianInput = s
+        .parse()
+        .with_context(|| format!("Invalid Gaussian input file {path:?}"))?;
+    // only lines in molecule specification will be updated
+    let natoms = input.molecule_spec().iter().filter(|line| re.is_match(line)).count();
+    if natoms != coords.len() {
+        let e = GaussianError::InconsistentGeometry {
+            input: natoms,
+            output: coords.len(),
         };
-
-        lines.push(line);
+        return Err(Error::from(e).context(format!("Failed to update {path:?} with new coords")));
+    }
+    for (line, [x, y, z]) in input
+        .molecule_spec_mut()
+        .iter_mut()
+        .filter(|line| re.is_match(line))
+        .zip(coords)
+    {
+        let xyz_old = re.find(line).unwrap().as_str();
+        let xyz_new = format!("{:20.8}{:20.8}{:20.8}", x, y, z);
+        *line = line.replace(xyz_old, &xyz_new);
     }
-    assert_eq!(ireplaced, coords.len(), "Incorrect number of coordinates!");
 
     // append final blank line to avoid the bug in Gaussian.
-    lines.push("".into());
+    let mut txt = input.to_string();
+    if !txt.ends_with('\n') {
+        txt.push('\n');
+    }
 
-    Ok(lines.join("\n"))
+    Ok(txt)
+}
+
+#[test]
+fn test_update_with_coordinates() -> Result<()> {
+    let coords = [[0.0, 0.0, 0.1], [0.0, 0.9, -0.2], [0.8, -0.4, -0.2], [-0.8, -0.4, -0.2]];
+    let txt = update_with_coordinates("tests/files/Test006.gjf", &coords, false)?;
+    let input: GaussianInput = txt.parse()?;
+    assert_eq!(
+        input.molecule_spec()[1],
+        " H          0.00000000          0.90000000         -0.20000000"
+    );
+    // basis set data should be untouched
+    assert_eq!(
+        input.trailing,
+        GaussianInput::from_file("tests/files/Test006.gjf")?.trailing
+    );
+
+    let e = update_with_coordinates("tests/files/Test006.gjf", &coords[..3], false).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::InconsistentGeometry { input: 4, output: 3 })
+    ));
+
+    Ok(())
 }
 // 6c25fcb8 ends here
 
@@ -162,6 +96,11 @@ struct Cli {
     #[structopt(short = 'o')]
     out_file: Option<PathBuf>,
 
+    /// Keep include lines like "@basis.gbs /N" in Gaussian input file
+    /// untouched, instead of inlining the included files.
+    #[structopt(long)]
+    keep_includes: bool,
+
     /// The Gaussian log file containing multiple geometries, such as a geometry
     /// optimization job.
     log_file: PathBuf,
@@ -185,7 +124,7 @@ pub fn enter_main() -> Result<()> {
     let coords = parse_gaussian_log_file(ofile)?;
     info!("Found coordinates for {} atoms.", coords.len());
 
-    let txt = update_with_coordinates(&ifile, &coords)?;
+    let txt = update_with_coordinates(&ifile, &coords, args.keep_includes)?;
 
     if let Some(ofile) = args.out_file {
         gut::fs::write_to_file(ofile, &txt)?;
@@ -2,6 +2,7 @@
 #![allow(non_camel_case_types)]
 
 use super::*;
+use clap::ValueEnum;
 // 3b069e29 ends here
 
 // [[file:../../xo-tools.note::d3061f91][d3061f91]]
@@ -11,18 +12,170 @@ enum Action {
     Rewrite(RewriteInput),
     /// Obtain energy of XYG3 type functional from relevant Gaussian output file
     Obtain(ObtainFrom),
+    /// Rewrite Gaussian input, run Gaussian and obtain energy of XYG3 type
+    /// functional in one go
+    Run(RunJob),
+    /// Fit xDH parameters against reference energies by least squares
+    Fit(FitWith),
+    /// Report reaction energies from Gaussian outputs of species
+    Reaction(ReactionOf),
 }
 
 #[derive(Args, Debug)]
 struct RewriteInput {
     /// Path to Gaussian input file containing "XYG3" or other xDH functional keywords
     inpfile: Option<PathBuf>,
+
+    /// Keep include lines like "@basis.gbs /N" untouched, instead of
+    /// inlining the included files.
+    #[clap(long)]
+    keep_includes: bool,
+
+    /// The Gaussian version for extra overlays of the MP2 step: g03, g09
+    /// or g16.
+    #[clap(long, default_value = "g09")]
+    gaussian_version: crate::xdh::GaussianVersion,
 }
 
 #[derive(Args, Debug)]
 struct ObtainFrom {
     /// Path to Gaussian output file relevant for XYG3 type calculations
     outfile: Option<PathBuf>,
+
+    /// Follow the Gaussian output while being written, showing the energy
+    /// terms still missing, until all of them are collected.
+    #[clap(long, requires = "outfile")]
+    follow: bool,
+
+    /// Stop following if the output is not written for this many seconds,
+    /// as for a job killed by scheduler without termination. 0 to wait
+    /// forever.
+    #[clap(long, default_value = "3600", requires = "follow")]
+    timeout: u64,
+
+    /// Report results at every geometry in a relaxed scan, IRC or
+    /// multi-step job, instead of the last one only.
+    #[clap(long, conflicts_with_all = ["follow", "all"])]
+    scan: bool,
+
+    #[clap(flatten)]
+    report: ReportWith,
+}
+
+#[derive(Args, Debug)]
+struct RunJob {
+    /// Path to Gaussian input file containing "XYG3" or other xDH functional keywords
+    inpfile: PathBuf,
+
+    /// Path to Gaussian output file. If not set, it will be placed next to
+    /// input file with ".log" extension.
+    #[clap(short = 'o', long)]
+    outfile: Option<PathBuf>,
+
+    /// The main Gaussian executable name: g03, g09, g16, ...
+    #[clap(short = 'x', long, default_value = "g09")]
+    gauss_exe: String,
+
+    /// The Gaussian version for extra overlays of the MP2 step: g03, g09
+    /// or g16. If not set, it is inferred from the executable name.
+    #[clap(long)]
+    gaussian_version: Option<crate::xdh::GaussianVersion>,
+
+    /// Keep include lines like "@basis.gbs /N" untouched, instead of
+    /// inlining the included files.
+    #[clap(long)]
+    keep_includes: bool,
+
+    #[clap(flatten)]
+    report: ReportWith,
+}
+
+/// Options for reporting xDH results
+#[derive(Args, Debug)]
+struct ReportWith {
+    /// The xDH functional to report: XYG3, XYG5, XYG6, XYG7, XYGJ-OS,
+    /// revXYG3 or a functional defined in presets file. If not set, the
+    /// functional requested in Gaussian input will be used.
+    #[clap(short = 'f', long)]
+    functional: Option<String>,
+
+    /// Report a custom functional using seven comma separated parameters
+    /// for Ex(HF), Ex(S), Ex(B), Ec(VWN), Ec(LYP), E2(os) and E2(ss).
+    #[clap(short = 'p', long, conflicts_with = "functional")]
+    parameters: Option<String>,
+
+    /// Path to a TOML or JSON file defining named parameters for custom
+    /// functionals. If not set, the file specified by XDH_PRESETS
+    /// environment variable will be used.
+    #[clap(long)]
+    presets: Option<PathBuf>,
+
+    /// Report energies of all xDH functionals including the ones defined in
+    /// presets file, the B3LYP reference energy and the energy components.
+    #[clap(short = 'a', long)]
+    all: bool,
+
+    /// Warn if the deviation of <S**2> from the exact value in open-shell
+    /// reference exceeds this threshold.
+    #[clap(long, default_value = "0.1")]
+    s2_threshold: f64,
+
+    /// The output format.
+    #[clap(long, value_enum, default_value = "model")]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct FitWith {
+    /// Dataset files, each line containing a reference energy in Hartree
+    /// followed by a reaction, such as "-0.0412 2*h2.log + o2.log ->
+    /// 2*h2o.log"
+    #[clap(required = true)]
+    datasets: Vec<PathBuf>,
+
+    /// Linear constraint on parameters, such as "Ex(HF)+Ex(S)+Ex(B)=1" or
+    /// "Ec(VWN)=0". Parameters can also be referred by position like "p3".
+    /// Can be specified multiple times.
+    #[clap(short = 'c', long)]
+    constraint: Vec<crate::xdh::Constraint>,
+
+    /// The xDH functional to compare with.
+    #[clap(short = 'f', long, default_value = "XYG3")]
+    functional: crate::xdh::Functional,
+
+    /// The unit of fitting errors: kcal/mol, kJ/mol, eV or Hartree.
+    #[clap(long, default_value = "kcal/mol")]
+    unit: crate::xdh::Unit,
+}
+
+#[derive(Args, Debug)]
+struct ReactionOf {
+    /// Reactions defined by Gaussian output files of species with optional
+    /// stoichiometric coefficients, such as "2*h2.log + o2.log -> 2*h2o.log"
+    #[clap(required = true)]
+    reactions: Vec<crate::xdh::Reaction>,
+
+    /// Path to a TOML or JSON file defining named parameters for custom
+    /// functionals. If not set, the file specified by XDH_PRESETS
+    /// environment variable will be used.
+    #[clap(long)]
+    presets: Option<PathBuf>,
+}
+
+/// Output format for `xdh obtain`
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// The model properties format for the requested functional, and a
+    /// human-readable summary in stderr
+    Model,
+    /// Human-readable text
+    Text,
+    /// Comma-separated values
+    Csv,
+    /// JSON with a stable schema
+    Json,
+    /// TOML with the same schema as JSON
+    Toml,
 }
 
 /// Obtain XYG3-type doubly hybrid (xDH) results from Gaussian output
@@ -36,6 +189,94 @@ struct Cli {
     action: Action,
 }
 
+/// Read presets from `f` or from the file specified by XDH_PRESETS
+/// environment variable.
+fn read_presets(f: Option<PathBuf>) -> Result<crate::xdh::Presets> {
+    use crate::xdh::Presets;
+
+    match f.or(std::env::var_os("XDH_PRESETS").map(PathBuf::from)) {
+        Some(f) => Presets::from_file(&f).with_context(|| format!("Failed to read presets from {f:?}")),
+        None => Ok(Presets::default()),
+    }
+}
+
+impl ReportWith {
+    /// Return the functional to report for `xdh` followed by functionals
+    /// defined in presets if all energies are requested.
+    fn functionals(&self, xdh: &crate::xdh::xDH) -> Result<Vec<crate::xdh::CustomFunctional>> {
+        use crate::xdh::{CustomFunctional, Functional};
+
+        let presets = read_presets(self.presets.clone())?;
+        let functional = if let Some(params) = &self.parameters {
+            CustomFunctional::from_csv("custom", params)?
+        } else if let Some(name) = &self.functional {
+            presets.find(name)?
+        } else if let Some(functional) = xdh.requested_functional() {
+            functional.into()
+        } else {
+            warn!("no requested xDH functional recorded in Gaussian output, report XYG3 energy instead.");
+            warn!("use -f option to report other xDH functional.");
+            Functional::XYG3.into()
+        };
+        let mut functionals = vec![functional];
+        if self.all {
+            functionals.extend(presets.functionals());
+        }
+        Ok(functionals)
+    }
+
+    /// Print xDH results in requested format.
+    fn print(&self, xdh: &crate::xdh::xDH) -> Result<()> {
+        if let Some(msg) = xdh.check_spin_contamination(self.s2_threshold) {
+            warn!("{msg}: xDH energies could be untrustworthy!");
+        }
+        let functionals = self.functionals(xdh)?;
+        match self.format {
+            Format::Model => {
+                let energy = xdh.energy_with(&functionals[0].parameters);
+                eprint!("{}", xdh.report_text(&functionals, self.all));
+                println!("@model_properties_format_version 0.1");
+                println!("# {} energy: convert from a.u. to eV", functionals[0].name);
+                println!("@energy unit_factor=27.211386024367243");
+                println!("{energy:16.8}");
+            }
+            Format::Text => print!("{}", xdh.report_text(&functionals, self.all)),
+            Format::Csv => print!("{}", xdh.report_csv(&functionals, self.all)),
+            Format::Json => println!("{}", xdh.report(&functionals[0], &functionals[1..]).to_json()?),
+            Format::Toml => print!("{}", xdh.report(&functionals[0], &functionals[1..]).to_toml()?),
+        }
+        Ok(())
+    }
+}
+
+/// Rewrite Gaussian input for xDH calculation, run Gaussian with output
+/// written in a log file, and collect xDH results from it.
+fn run_xdh_job(job: &RunJob) -> Result<crate::xdh::xDH> {
+    use crate::runner::{gaussian_rc_file, guess_output_file};
+    use crate::xdh::{xDH, RewriteOptions};
+
+    // the Gaussian version is inferred from executable name if not set
+    let version = match job.gaussian_version {
+        Some(version) => version,
+        None => job.gauss_exe.parse().with_context(|| {
+            format!(
+                "cannot infer Gaussian version from {:?}, use --gaussian-version",
+                job.gauss_exe
+            )
+        })?,
+    };
+    let options = RewriteOptions {
+        keep_includes: job.keep_includes,
+        version,
+    };
+    let outfile = match &job.outfile {
+        Some(f) => f.clone(),
+        None => guess_output_file(&job.inpfile)?,
+    };
+    let rcfile = gaussian_rc_file(&job.gauss_exe)?;
+    xDH::run_gaussian(&job.inpfile, &outfile, &rcfile, options)
+}
+
 pub fn enter_main() -> Result<()> {
     use crate::xdh::*;
     use duct::cmd;
@@ -46,18 +287,98 @@ pub fn enter_main() -> Result<()> {
     match args.action {
         Action::Rewrite(rewrite) => {
             let inp: Option<&Path> = rewrite.inpfile.as_ref().map(|x| x.as_ref());
-            let s = xDH::rewrite_gaussian_input(inp)?;
+            let options = RewriteOptions {
+                keep_includes: rewrite.keep_includes,
+                version: rewrite.gaussian_version,
+            };
+            let s = xDH::rewrite_gaussian_input(inp, options)?;
             println!("{s}");
         }
+        Action::Fit(fit) => {
+            let datasets: Vec<_> = fit.datasets.iter().map(Dataset::from_file).try_collect()?;
+            let fitting = Fitting::new(&datasets)?;
+            let params = fitting.fit(&fit.constraint)?;
+            println!("Fitted parameters:");
+            for (name, p) in COMPONENT_NAMES.iter().zip(params) {
+                println!("  {name:<10} = {p:12.6}");
+            }
+            println!("  --parameters {}", params.iter().map(|p| format!("{p:.6}")).join(","));
+
+            let functional = fit.functional;
+            let errors = fitting.errors(&params);
+            let errors_ref = fitting.errors(&functional.parameters());
+            let unit = fit.unit;
+            println!("\nErrors in {unit}:");
+            println!(
+                "  {:<16} {:>6} {:>12} {:>12} {:>12} {:>12}",
+                "dataset", "count", "MAE", "RMSE", "MAE/ref", "RMSE/ref"
+            );
+            let digits = if unit == Unit::Hartree { 8 } else { 4 };
+            for (e, r) in errors.iter().zip(&errors_ref) {
+                let [mae, rmse, mae_ref, rmse_ref] = [e.mae, e.rmse, r.mae, r.rmse].map(|x| unit.convert(x));
+                println!(
+                    "  {:<16} {:>6} {mae:>12.digits$} {rmse:>12.digits$} {mae_ref:>12.digits$} {rmse_ref:>12.digits$}",
+                    e.name, e.count
+                );
+            }
+            println!("  (ref: {functional})");
+        }
+        Action::Reaction(r) => {
+            let presets = read_presets(r.presets)?;
+            for reaction in r.reactions {
+                let xdh = reaction.collect()?;
+                let equation = reaction
+                    .species
+                    .iter()
+                    .map(|(coef, path)| format!("{coef}*{}", path.display()))
+                    .join(" ");
+                println!("Reaction energies of {equation}:");
+                print!("{}", xdh.report_relative_energies(&presets.functionals()));
+            }
+        }
+        Action::Obtain(obtain) if obtain.scan => {
+            let out: Option<&Path> = obtain.outfile.as_ref().map(|x| x.as_ref());
+            let points = xDH::collect_all_from_gaussian(out)?;
+            let report = &obtain.report;
+            for (i, xdh) in points.iter().enumerate() {
+                if let Some(msg) = xdh.check_spin_contamination(report.s2_threshold) {
+                    warn!("point {}: {msg}", i + 1);
+                }
+            }
+            let functional = report.functionals(&points[0])?.remove(0);
+            match report.format {
+                Format::Model | Format::Text => print!("{}", xDH::report_scan_text(&points, &functional)),
+                Format::Csv => print!("{}", xDH::report_scan_csv(&points, &functional)),
+                Format::Json => println!("{}", xDH::report_scan(&points, &functional, &[]).to_json()?),
+                Format::Toml => print!("{}", xDH::report_scan(&points, &functional, &[]).to_toml()?),
+            }
+        }
         Action::Obtain(obtain) => {
             let out: Option<&Path> = obtain.outfile.as_ref().map(|x| x.as_ref());
-            let xdh = xDH::collect_from_gaussian(out)?;
-            let energy_xyg3 = xdh.energy(Functional::XYG3);
-            eprintln!("  E(XYG3)    =  {energy_xyg3:16.8} A.U.");
-            println!("@model_properties_format_version 0.1");
-            println!("# XYG3 energy: convert from a.u. to eV");
-            println!("@energy unit_factor=27.211386024367243");
-            println!("{energy_xyg3:16.8}");
+            let xdh = match out {
+                Some(f) if obtain.follow => {
+                    let mut last = None;
+                    let interval = std::time::Duration::from_secs(2);
+                    let timeout = (obtain.timeout > 0).then(|| std::time::Duration::from_secs(obtain.timeout));
+                    xDH::follow_gaussian(f, interval, timeout, |collector| {
+                        let missing = collector.missing();
+                        if last.as_ref() != Some(&missing) {
+                            if missing.is_empty() {
+                                eprintln!("all energy terms collected.");
+                            } else {
+                                eprintln!("missing energy terms: {}", missing.join(", "));
+                            }
+                            last = Some(missing);
+                        }
+                    })?
+                }
+                _ => xDH::collect_from_gaussian(out)?,
+            };
+            obtain.report.print(&xdh)?;
+        }
+        Action::Run(job) => {
+            let xdh = run_xdh_job(&job)?;
+            job.report.print(&xdh)?;
         }
     }
 
@@ -0,0 +1,124 @@
+// [[file:../xo-tools.note::3b8e61d4][3b8e61d4]]
+//! Typed errors for parsing and running Gaussian calculations
+//!
+//! Functions in this crate return `anyhow` errors with context, in which
+//! the root cause of failure is a [`GaussianError`] if it is known. Batch
+//! drivers can find it with [`GaussianError::find`] to react per failure
+//! kind, and command line tools exit with code from [`exit_code`].
+
+use super::*;
+
+use std::process::ExitCode;
+// 3b8e61d4 ends here
+
+// [[file:../xo-tools.note::e90c4f27][e90c4f27]]
+/// Failure kinds in parsing Gaussian files and running Gaussian jobs
+#[derive(Debug, thiserror::Error)]
+pub enum GaussianError {
+    /// Failed to open or read a file
+    #[error("Could not read file {path:?}")]
+    IoError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Invalid content at 1-based `line` of `file`
+    #[error("{}:{line}: {message}", file.display())]
+    ParseError {
+        file: PathBuf,
+        line: usize,
+        message: String,
+    },
+
+    /// Invalid content without a known position in file, such as a route
+    /// section or a constraint from command line, or Gaussian output
+    /// lacking required lines
+    #[error("{0}")]
+    InvalidFormat(String),
+
+    /// Gaussian program exits with error, with the last lines in `output`
+    #[error("Gaussian terminated abnormally, see {output:?}:\n{tail}")]
+    GaussianTerminatedAbnormally { output: PathBuf, tail: String },
+
+    /// The number of atoms in Gaussian output is different from the input
+    #[error("output coords is inconsistent with input structure: {input} atoms in input, {output} in output")]
+    InconsistentGeometry { input: usize, output: usize },
+
+    /// The file name is not usable as required, such as an input file with
+    /// ".log" extension
+    #[error("invalid file name: {0:?}")]
+    InvalidFileName(PathBuf),
+}
+
+impl GaussianError {
+    /// Find the root cause of failure in the chain of `err`.
+    pub fn find(err: &Error) -> Option<&Self> {
+        // context attached to anyhow error is not visible in the chain
+        err.downcast_ref::<Self>()
+            .or_else(|| err.chain().find_map(|e| e.downcast_ref::<Self>()))
+    }
+
+    /// The process exit code for this failure kind. 1 is for unknown
+    /// failures, and 2 for invalid command line arguments.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::IoError { .. } => 3,
+            Self::ParseError { .. } | Self::InvalidFormat(_) => 4,
+            Self::GaussianTerminatedAbnormally { .. } => 5,
+            Self::InconsistentGeometry { .. } => 6,
+            Self::InvalidFileName(_) => 7,
+        }
+    }
+}
+
+/// Return the process exit code for failure kind of `err`.
+pub fn exit_code(err: &Error) -> u8 {
+    if let Some(e) = GaussianError::find(err) {
+        e.exit_code()
+    } else if err.chain().any(|e| e.is::<std::io::Error>()) {
+        // the same as `GaussianError::IoError`
+        3
+    } else {
+        1
+    }
+}
+
+/// Report the error in `result` if any, and return exit code for it. This
+/// is intended for `main` functions of command line tools.
+pub fn report(result: Result<()>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+// e90c4f27 ends here
+
+// [[file:../xo-tools.note::5a2d7c90][5a2d7c90]]
+#[test]
+fn test_exit_code() {
+    let e: Error = GaussianError::InconsistentGeometry { input: 3, output: 4 }.into();
+    let e = e.context("Failed to update input");
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::InconsistentGeometry { input: 3, output: 4 })
+    ));
+    assert_eq!(exit_code(&e), 6);
+
+    let Err(e) = file_reader("tests/files/missing.log") else {
+        panic!("missing file opened");
+    };
+    assert!(matches!(GaussianError::find(&e), Some(GaussianError::IoError { .. })));
+    assert_eq!(exit_code(&e), 3);
+
+    let e: Error = GaussianError::InvalidFormat("invalid route section".into()).into();
+    assert_eq!(exit_code(&e.context("Invalid Gaussian input")), 4);
+
+    let e: Error = std::io::Error::from(std::io::ErrorKind::BrokenPipe).into();
+    assert_eq!(exit_code(&e), 3);
+    assert_eq!(exit_code(&format_err!("unknown")), 1);
+}
+// 5a2d7c90 ends here
@@ -0,0 +1,500 @@
+// [[file:../xo-tools.note::d3631a7e][d3631a7e]]
+//! Typed representation of Gaussian input file
+//!
+//! Reference: https://gaussian.com/input/
+
+use super::*;
+
+use crate::error::GaussianError;
+// d3631a7e ends here
+
+// [[file:../xo-tools.note::b597d889][b597d889]]
+mod route;
+
+pub use route::{IOp, Keyword, Route};
+// b597d889 ends here
+
+// [[file:../xo-tools.note::25637602][25637602]]
+/// A blank line terminated section in Gaussian input, such as title section
+/// or gen basis set data. The raw blank lines are kept for writing the input
+/// back without any change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Section {
+    /// Non-blank lines in this section
+    pub lines: Vec<String>,
+    /// Blank lines (possibly containing white spaces) terminating this section
+    blanks: Vec<String>,
+}
+
+impl Section {
+    /// Create a new section from `lines`. A blank line will be inserted
+    /// automatically when writing if it is followed by other sections.
+    pub fn new<S: Into<String>>(lines: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            lines: lines.into_iter().map(|x| x.into()).collect(),
+            blanks: vec![],
+        }
+    }
+
+    /// Terminate this section with exactly one blank line, dropping redundant
+    /// trailing blank lines.
+    pub fn terminate(&mut self) {
+        self.blanks.truncate(1);
+        if self.blanks.is_empty() {
+            self.blanks.push("".into());
+        }
+    }
+
+    /// All lines joined by a single space, which is suitable for parsing
+    /// multi-line route section.
+    pub fn joined(&self) -> String {
+        self.lines.iter().map(|x| x.trim()).join(" ")
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Split `lines` into blank line terminated sections.
+fn split_sections<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<Section> {
+    let mut sections = vec![];
+    let mut section = Section::default();
+    for line in lines {
+        if is_blank(line) {
+            section.blanks.push(line.into());
+        } else {
+            if !section.blanks.is_empty() {
+                sections.push(std::mem::take(&mut section));
+            }
+            section.lines.push(line.into());
+        }
+    }
+    if !section.lines.is_empty() || !section.blanks.is_empty() {
+        sections.push(section);
+    }
+    sections
+}
+// 25637602 ends here
+
+// [[file:../xo-tools.note::6349a213][6349a213]]
+/// Gaussian input for a single job step.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GaussianInput {
+    /// Link 0 commands, such as `%chk=foo.chk` and `%mem=2GB`. Any line
+    /// before the route section is kept here.
+    pub link0: Vec<String>,
+    /// The route section, which could span multiple lines.
+    pub route: Section,
+    /// Additional overlay cards requested by `extraoverlay` keyword.
+    pub overlay: Option<Section>,
+    /// The title section, which is absent for `geom=allcheck` job.
+    pub title: Option<Section>,
+    /// The molecule specification section, starting with the charge and
+    /// spin multiplicity line.
+    pub molecule: Option<Section>,
+    /// Other sections following the molecule specification, such as
+    /// Z-matrix variables, connectivity, gen basis set, ModRedundant or PCM
+    /// parameters.
+    pub trailing: Vec<Section>,
+    final_newline: bool,
+}
+
+impl std::str::FromStr for GaussianInput {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let final_newline = s.ends_with('\n');
+        let mut lines: Vec<_> = s.split('\n').collect();
+        if final_newline {
+            lines.pop();
+        }
+
+        // link0 lines are the lines before route section, and for route
+        // lines, the first line must be starting with "#"
+        let i = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with('#'))
+            .ok_or_else(|| GaussianError::InvalidFormat(format!("no route section found in Gaussian input: {s:?}")))?;
+        let link0 = lines[..i].iter().map(|&x| x.to_owned()).collect();
+
+        let mut sections = split_sections(lines[i..].iter().copied()).into_iter();
+        let route = sections.next().expect("route section");
+        let keywords: Route = route.joined().parse()?;
+
+        let overlay = if keywords.extra_overlay() {
+            sections.next()
+        } else {
+            None
+        };
+        let (title, molecule) = if keywords.geom_allcheck() {
+            (None, None)
+        } else {
+            (sections.next(), sections.next())
+        };
+
+        let input = Self {
+            link0,
+            route,
+            overlay,
+            title,
+            molecule,
+            trailing: sections.collect(),
+            final_newline,
+        };
+
+        Ok(input)
+    }
+}
+
+impl std::fmt::Display for GaussianInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sections = std::iter::once(&self.route)
+            .chain(&self.overlay)
+            .chain(&self.title)
+            .chain(&self.molecule)
+            .chain(&self.trailing)
+            .collect_vec();
+
+        let mut lines: Vec<&str> = self.link0.iter().map(|x| x.as_str()).collect();
+        let n = sections.len();
+        for (i, section) in sections.into_iter().enumerate() {
+            lines.extend(section.lines.iter().map(|x| x.as_str()));
+            lines.extend(section.blanks.iter().map(|x| x.as_str()));
+            // sections must be separated by a blank line
+            if section.blanks.is_empty() && i + 1 < n {
+                lines.push("");
+            }
+        }
+
+        let mut s = lines.join("\n");
+        if self.final_newline {
+            s.push('\n');
+        }
+        write!(f, "{s}")
+    }
+}
+// 6349a213 ends here
+
+// [[file:../xo-tools.note::3701e636][3701e636]]
+impl GaussianInput {
+    /// Read Gaussian input from a file, with files in include lines inlined.
+    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<Self> {
+        let f = f.as_ref();
+        let s = read_input_file(f, false)?;
+        s.parse().with_context(|| format!("Invalid Gaussian input file {f:?}"))
+    }
+
+    /// Parse keywords in route section.
+    pub fn parse_route(&self) -> Result<Route> {
+        self.route.joined().parse()
+    }
+
+    /// The charge and spin multiplicity line in molecule specification.
+    pub fn charge_multiplicity(&self) -> Option<&str> {
+        let molecule = self.molecule.as_ref()?;
+        molecule.lines.first().map(|x| x.as_str())
+    }
+
+    /// Return the charge and spin multiplicity of the whole system. For
+    /// ONIOM job, it is the first pair.
+    pub fn charge_and_multiplicity(&self) -> Option<(isize, usize)> {
+        let line = self.charge_multiplicity()?;
+        let mut parts = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|x| !x.is_empty());
+        let charge = parts.next()?.parse().ok()?;
+        let multiplicity = parts.next()?.parse().ok()?;
+        Some((charge, multiplicity))
+    }
+
+    /// Lines for atoms in molecule specification, excluding the charge and
+    /// spin multiplicity line.
+    pub fn molecule_spec(&self) -> &[String] {
+        match &self.molecule {
+            Some(molecule) if !molecule.lines.is_empty() => &molecule.lines[1..],
+            _ => &[],
+        }
+    }
+
+    /// Mutable access to lines for atoms in molecule specification.
+    pub fn molecule_spec_mut(&mut self) -> &mut [String] {
+        match &mut self.molecule {
+            Some(molecule) if !molecule.lines.is_empty() => &mut molecule.lines[1..],
+            _ => &mut [],
+        }
+    }
+
+    /// Append a new section at the end of input. The previous last section
+    /// will be terminated by a single blank line, and the final new line
+    /// will be always written.
+    pub fn push_section(&mut self, section: Section) {
+        let last = self
+            .trailing
+            .last_mut()
+            .or(self.molecule.as_mut())
+            .or(self.title.as_mut())
+            .or(self.overlay.as_mut())
+            .unwrap_or(&mut self.route);
+        last.terminate();
+        self.trailing.push(section);
+        self.final_newline = true;
+    }
+}
+// 3701e636 ends here
+
+// [[file:../xo-tools.note::53d6c40c][53d6c40c]]
+/// Gaussian input containing one or more job steps separated by `--Link1--`
+/// lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GaussianJobs {
+    /// Job steps in the order of execution
+    pub steps: Vec<GaussianInput>,
+    // raw `--Link1--` lines
+    separators: Vec<String>,
+}
+
+fn is_link1(line: &str) -> bool {
+    line.trim().eq_ignore_ascii_case("--link1--")
+}
+
+impl std::str::FromStr for GaussianJobs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut steps = vec![];
+        let mut separators = vec![];
+        let mut step_start = 0;
+        let mut line_start = 0;
+        for line in s.split_inclusive('\n') {
+            if is_link1(line) {
+                let step = s[step_start..line_start]
+                    .parse()
+                    .with_context(|| format!("invalid Gaussian input in job step {}", steps.len() + 1))?;
+                steps.push(step);
+                separators.push(line.trim_end_matches('\n').to_owned());
+                step_start = line_start + line.len();
+            }
+            line_start += line.len();
+        }
+        let step = s[step_start..]
+            .parse()
+            .with_context(|| format!("invalid Gaussian input in job step {}", steps.len() + 1))?;
+        steps.push(step);
+
+        Ok(Self { steps, separators })
+    }
+}
+
+impl std::fmt::Display for GaussianJobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                let link1 = self.separators.get(i - 1).map(|x| x.as_str()).unwrap_or("--Link1--");
+                writeln!(f, "{link1}")?;
+            }
+            let step = step.to_string();
+            write!(f, "{step}")?;
+            // Gaussian requires a blank line before `--Link1--`, which could
+            // be missing after a section pushed at the end
+            if i + 1 < self.steps.len() {
+                if !step.ends_with('\n') {
+                    writeln!(f)?;
+                }
+                if !step.lines().last().is_some_and(is_blank) {
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GaussianJobs {
+    /// Read Gaussian input with multiple job steps from a file, with files
+    /// in include lines inlined.
+    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<Self> {
+        let f = f.as_ref();
+        let s = read_input_file(f, false)?;
+        s.parse().with_context(|| format!("Invalid Gaussian input file {f:?}"))
+    }
+}
+// 53d6c40c ends here
+
+// [[file:../xo-tools.note::e2b7c9a4][e2b7c9a4]]
+/// An include line in Gaussian input, such as "@Test008.H /N", for reading
+/// input lines from an external file. With "/N" flag, the contents of the
+/// file will not be echoed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    pub path: PathBuf,
+    pub no_echo: bool,
+}
+
+impl Include {
+    /// Parse an include line. Return None if `line` is not an include line.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.trim().strip_prefix('@')?.split_whitespace();
+        let path = parts.next()?;
+        // the flag could be appended to the path directly: "@basis.gbs/N"
+        let (path, mut no_echo) = match path.strip_suffix("/N").or_else(|| path.strip_suffix("/n")) {
+            Some(p) if !p.is_empty() => (p, true),
+            _ => (path, false),
+        };
+        no_echo |= parts.any(|x| x.eq_ignore_ascii_case("/N"));
+        Some(Self {
+            path: path.into(),
+            no_echo,
+        })
+    }
+}
+
+/// Inline the files referenced by include lines in Gaussian input `s`.
+/// Relative paths are resolved against `base_dir`. The contents of included
+/// files are echoed in log unless "/N" flag is given.
+pub fn resolve_includes(s: &str, base_dir: &Path) -> Result<String> {
+    let mut resolved = String::new();
+    for line in s.split_inclusive('\n') {
+        let Some(include) = Include::parse(line) else {
+            resolved.push_str(line);
+            continue;
+        };
+        let path = base_dir.join(&include.path);
+        let content = gut::fs::read_file(&path).with_context(|| format!("Could not read include file {path:?}"))?;
+        if include.no_echo {
+            debug!("inlined include file {path:?}");
+        } else {
+            info!("inlined include file {path:?}:\n{content}");
+        }
+        resolved.push_str(&content);
+        if !content.is_empty() && !content.ends_with('\n') {
+            resolved.push('\n');
+        }
+    }
+    Ok(resolved)
+}
+
+/// Read Gaussian input from file `f`. Include lines are resolved relative
+/// to the directory of `f`, or kept untouched if `keep_includes` is true.
+pub fn read_input_file(f: &Path, keep_includes: bool) -> Result<String> {
+    let s = gut::fs::read_file(f)?;
+    if keep_includes {
+        Ok(s)
+    } else {
+        let base_dir = f.parent().unwrap_or(Path::new(""));
+        resolve_includes(&s, base_dir).with_context(|| format!("Failed to resolve include lines in {f:?}"))
+    }
+}
+// e2b7c9a4 ends here
+
+// [[file:../xo-tools.note::1eec628a][1eec628a]]
+#[test]
+fn test_gaussian_input_roundtrip() -> Result<()> {
+    for f in std::fs::read_dir("tests/files")? {
+        let f = f?.path();
+        let ext = f.extension().and_then(|x| x.to_str());
+        if matches!(ext, Some("gjf") | Some("com")) {
+            let s = gut::fs::read_file(&f)?;
+            let input: GaussianInput = s.parse()?;
+            assert_eq!(input.to_string(), s, "roundtrip failed for {f:?}");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gaussian_input_sections() -> Result<()> {
+    let input = GaussianInput::from_file("tests/files/Job_o2.com")?;
+    assert_eq!(input.link0.len(), 3);
+    assert_eq!(input.route.lines.len(), 1);
+    assert_eq!(input.overlay.as_ref().unwrap().lines.len(), 3);
+    assert_eq!(input.title.as_ref().unwrap().lines, ["Title Card Required"]);
+    assert_eq!(input.charge_and_multiplicity(), Some((0, 3)));
+    assert_eq!(input.molecule_spec().len(), 2);
+    assert_eq!(input.trailing.len(), 2);
+
+    let input = GaussianInput::from_file("tests/files/Test005.gjf")?;
+    assert!(input.link0.is_empty());
+    assert!(input.overlay.is_none());
+    assert_eq!(input.title.as_ref().unwrap().lines.len(), 4);
+    assert_eq!(input.molecule_spec().len(), 4);
+    assert_eq!(input.trailing[0].lines, ["B1 = 1.014256", "A1 107.5921", "C1,116.458"]);
+
+    let input = GaussianInput::from_file("tests/files/Test009.gjf")?;
+    assert_eq!(input.charge_and_multiplicity(), Some((0, 1)));
+    assert!(input.trailing.is_empty());
+
+    let input: GaussianInput = "%chk=x.chk\n# b3lyp geom=allcheck guess=read\n\n".parse()?;
+    assert!(input.title.is_none());
+    assert!(input.molecule.is_none());
+
+    let e = "%chk=x.chk\n\nTitle\n\n0 1\nH\n".parse::<GaussianInput>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let e = "%chk=x.chk\n# b3lyp/gen scf=(tight\n\nTitle\n\n"
+        .parse::<GaussianInput>()
+        .unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+
+    // sections are split by route keywords, not by words in route
+    let input: GaussianInput = "# b3lyp Geom(AllCheck,NewRedundant) guess=read\n\n".parse()?;
+    assert!(input.title.is_none());
+    let input: GaussianInput = "# b3lyp geom=noallcheck\n\ntitle\n\n0 1\nH\n".parse()?;
+    assert_eq!(input.title.as_ref().unwrap().lines, ["title"]);
+    assert_eq!(input.charge_and_multiplicity(), Some((0, 1)));
+    let input: GaussianInput = "# b3lyp/extraoverlay-basis\n\ntitle\n\n0 1\nH\n".parse()?;
+    assert!(input.overlay.is_none());
+    assert_eq!(input.title.as_ref().unwrap().lines, ["title"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_gaussian_jobs() -> Result<()> {
+    let s = gut::fs::read_file("tests/files/Test011.gjf")?;
+    let jobs: GaussianJobs = s.parse()?;
+    assert_eq!(jobs.steps.len(), 2);
+    assert_eq!(jobs.to_string(), s);
+
+    let step2 = &jobs.steps[1];
+    assert_eq!(step2.link0, ["%chk=Test011.chk"]);
+    assert_eq!(step2.charge_multiplicity(), Some("0 1"));
+    assert!(step2.molecule_spec().is_empty());
+
+    let s = gut::fs::read_file("tests/files/Test001.gjf")?;
+    let jobs: GaussianJobs = s.parse()?;
+    assert_eq!(jobs.steps.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_gaussian_input_includes() -> Result<()> {
+    let include = Include::parse("@Test008.H  /N").unwrap();
+    assert_eq!(include.path, Path::new("Test008.H"));
+    assert!(include.no_echo);
+    assert!(!Include::parse(" @Test007.H").unwrap().no_echo);
+    let include = Include::parse("@basis.gbs/n").unwrap();
+    assert_eq!(include.path, Path::new("basis.gbs"));
+    assert!(include.no_echo);
+    assert!(Include::parse("N 0").is_none());
+
+    let f: &Path = "tests/files/Test008.gjf".as_ref();
+    let raw = read_input_file(f, true)?;
+    assert_eq!(raw, gut::fs::read_file(f)?);
+    let s = read_input_file(f, false)?;
+    let basis = gut::fs::read_file("tests/files/Test008.H")?;
+    assert_eq!(s, raw.replace("@Test008.H  /N\n", &basis));
+
+    // basis data in include file become part of the last section
+    let input = GaussianInput::from_file("tests/files/Test007.gjf")?;
+    let gen = input.trailing.last().unwrap();
+    assert_eq!(gen.lines.first().unwrap(), "N 0");
+    assert_eq!(gen.lines.last().unwrap(), "****");
+    assert!(gen.lines.contains(&"-H 0".to_owned()));
+
+    assert!(resolve_includes("@missing.H\n", "tests/files".as_ref()).is_err());
+
+    Ok(())
+}
+// 1eec628a ends here
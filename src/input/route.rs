@@ -0,0 +1,472 @@
+// [[file:../../xo-tools.note::ca50deda][ca50deda]]
+//! Keyword-aware parsing of Gaussian route section
+//!
+//! Reference: https://gaussian.com/route/
+
+use super::*;
+
+use crate::error::GaussianError;
+// ca50deda ends here
+
+// [[file:../../xo-tools.note::67f95022][67f95022]]
+/// An internal option set by `IOp(overlay/option=value)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IOp {
+    pub overlay: usize,
+    pub option: usize,
+    pub value: i64,
+}
+
+impl IOp {
+    pub fn new(overlay: usize, option: usize, value: i64) -> Self {
+        Self { overlay, option, value }
+    }
+}
+
+impl std::str::FromStr for IOp {
+    type Err = Error;
+
+    // 5/33=1
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = || -> Option<Self> {
+            let (overlay, s) = s.split_once('/')?;
+            let (option, value) = s.split_once('=')?;
+            let iop = Self {
+                overlay: overlay.trim().parse().ok()?,
+                option: option.trim().parse().ok()?,
+                value: value.trim().parse().ok()?,
+            };
+            Some(iop)
+        };
+        parse().ok_or_else(|| GaussianError::InvalidFormat(format!("invalid IOp: {s:?}")).into())
+    }
+}
+
+impl std::fmt::Display for IOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}={}", self.overlay, self.option, self.value)
+    }
+}
+
+/// A route keyword with its options, such as `SCF=(Tight,XQC)`, `NoSymm`,
+/// `SCRF(PCM,Solvent=Water)` or the method part `XYG3(FC)` in a
+/// method/basis pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyword {
+    pub name: String,
+    pub options: Vec<String>,
+    // options are specified using `keyword=option` or `keyword(option)`
+    equal_sign: bool,
+}
+
+impl Keyword {
+    /// Create a keyword without any options.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            options: vec![],
+            equal_sign: true,
+        }
+    }
+
+    /// Return true if the keyword name is `name`, ignoring ASCII case.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Return true if `option` is found in keyword options, ignoring ASCII
+    /// case.
+    pub fn has_option(&self, option: &str) -> bool {
+        self.options.iter().any(|x| x.eq_ignore_ascii_case(option))
+    }
+}
+
+impl std::str::FromStr for Keyword {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let i = s.find(['=', '(']).unwrap_or(s.len());
+        let (name, rest) = s.split_at(i);
+        if name.is_empty() {
+            bail!(GaussianError::InvalidFormat(format!("invalid route keyword: {s:?}")));
+        }
+        let (equal_sign, options) = if let Some(rest) = rest.strip_prefix('=') {
+            let rest = rest.strip_prefix('(').and_then(|x| x.strip_suffix(')')).unwrap_or(rest);
+            (true, rest)
+        } else if let Some(rest) = rest.strip_prefix('(') {
+            let rest = rest
+                .strip_suffix(')')
+                .ok_or_else(|| GaussianError::InvalidFormat(format!("invalid route keyword: {s:?}")))?;
+            (false, rest)
+        } else {
+            (true, "")
+        };
+
+        let kw = Self {
+            name: name.into(),
+            options: split_top_level(options, ',')
+                .into_iter()
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_owned())
+                .collect(),
+            equal_sign,
+        };
+        Ok(kw)
+    }
+}
+
+impl std::fmt::Display for Keyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = &self.name;
+        let options = self.options.join(",");
+        match (self.options.len(), self.equal_sign) {
+            (0, _) => write!(f, "{name}"),
+            (1, true) => write!(f, "{name}={options}"),
+            (_, true) => write!(f, "{name}=({options})"),
+            (_, false) => write!(f, "{name}({options})"),
+        }
+    }
+}
+
+/// An item in route section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteItem {
+    /// Method and basis set pair, such as `B3LYP/6-31G(d)` or `XYG3(FC)/gen`
+    MethodBasis(Keyword, String),
+    /// A group of internal options: `IOp(5/33=1,3/76=1000007400)`
+    IOp(Vec<IOp>),
+    /// Any other keyword, including method or basis set given separately
+    Keyword(Keyword),
+}
+
+impl std::str::FromStr for RouteItem {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // the slash in `IOp(5/33=1)` is not for method/basis pair
+        if let Some(i) = find_top_level(s, '/') {
+            let method = s[..i].parse()?;
+            return Ok(Self::MethodBasis(method, s[i + 1..].into()));
+        }
+
+        let kw: Keyword = s.parse()?;
+        if kw.is("iop") {
+            let iops: Result<Vec<IOp>> = kw.options.iter().map(|x| x.parse()).collect();
+            Ok(Self::IOp(iops?))
+        } else {
+            Ok(Self::Keyword(kw))
+        }
+    }
+}
+
+impl std::fmt::Display for RouteItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::MethodBasis(method, basis) => write!(f, "{method}/{basis}"),
+            Self::IOp(iops) => write!(f, "iop({})", iops.iter().join(",")),
+            Self::Keyword(kw) => write!(f, "{kw}"),
+        }
+    }
+}
+// 67f95022 ends here
+
+// [[file:../../xo-tools.note::77a3d32e][77a3d32e]]
+/// Return the position of the first `pat` char outside parentheses.
+fn find_top_level(s: &str, pat: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == pat && depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `s` by `pat` char outside parentheses.
+fn split_top_level(s: &str, pat: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut s = s;
+    while let Some(i) = find_top_level(s, pat) {
+        parts.push(&s[..i]);
+        s = &s[i + 1..];
+    }
+    parts.push(s);
+    parts
+}
+
+/// Split route text into keyword tokens. White spaces inside parentheses
+/// are removed, and spaces around `=` or before `(` are allowed, such as
+/// `SCF = (Tight, XQC)`.
+fn split_tokens(s: &str) -> Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut depth = 0usize;
+    for c in s.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| GaussianError::InvalidFormat(format!("unbalanced parentheses in route: {s:?}")))?
+            }
+            c if c.is_whitespace() => {
+                if depth == 0 && !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        token.push(c);
+    }
+    if depth != 0 {
+        bail!(GaussianError::InvalidFormat(format!(
+            "unbalanced parentheses in route: {s:?}"
+        )));
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    let mut merged: Vec<String> = vec![];
+    for token in tokens {
+        match merged.last_mut() {
+            Some(last) if last.ends_with('=') || token.starts_with('=') || token.starts_with('(') => {
+                last.push_str(&token)
+            }
+            _ => merged.push(token),
+        }
+    }
+    Ok(merged)
+}
+// 77a3d32e ends here
+
+// [[file:../../xo-tools.note::e3615c4e][e3615c4e]]
+/// The parsed route section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Route {
+    /// The print level following the pound sign: `N`, `P` or `T`
+    pub print_level: Option<char>,
+    /// Route keywords in the input order
+    pub items: Vec<RouteItem>,
+}
+
+impl std::str::FromStr for Route {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // a pound sign (#) as the first non-blank character of a line
+        let s = s
+            .trim_start()
+            .strip_prefix('#')
+            .ok_or_else(|| GaussianError::InvalidFormat(format!("invalid route section: {s:?}")))?;
+
+        let mut chars = s.chars();
+        let print_level = match (chars.next(), chars.next()) {
+            (Some(c), next) if "NPTnpt".contains(c) && next.filter(|x| !x.is_whitespace()).is_none() => {
+                Some(c.to_ascii_uppercase())
+            }
+            _ => None,
+        };
+        let s = if print_level.is_some() { &s[1..] } else { s };
+
+        let items: Result<Vec<_>> = split_tokens(s)?.iter().map(|x| x.parse()).collect();
+        let route = Self {
+            print_level,
+            items: items?,
+        };
+        Ok(route)
+    }
+}
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#")?;
+        if let Some(c) = self.print_level {
+            write!(f, "{c}")?;
+        }
+        for item in &self.items {
+            write!(f, " {item}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Route {
+    /// Iterate over all keywords, including the method in method/basis pair.
+    pub fn keywords(&self) -> impl Iterator<Item = &Keyword> {
+        self.items.iter().filter_map(|item| match item {
+            RouteItem::MethodBasis(kw, _) | RouteItem::Keyword(kw) => Some(kw),
+            RouteItem::IOp(_) => None,
+        })
+    }
+
+    /// Mutable access to all keywords, including the method in method/basis
+    /// pair.
+    pub fn keywords_mut(&mut self) -> impl Iterator<Item = &mut Keyword> {
+        self.items.iter_mut().filter_map(|item| match item {
+            RouteItem::MethodBasis(kw, _) | RouteItem::Keyword(kw) => Some(kw),
+            RouteItem::IOp(_) => None,
+        })
+    }
+
+    /// Find keyword by `name`, ignoring ASCII case.
+    pub fn find_keyword(&self, name: &str) -> Option<&Keyword> {
+        self.keywords().find(|kw| kw.is(name))
+    }
+
+    /// Return true if keyword `name` is found in route, ignoring ASCII case.
+    pub fn has_keyword(&self, name: &str) -> bool {
+        self.find_keyword(name).is_some()
+    }
+
+    /// Append keyword `name` if it is not found in route.
+    pub fn require_keyword(&mut self, name: &str) {
+        if !self.has_keyword(name) {
+            self.items.push(RouteItem::Keyword(Keyword::new(name)));
+        }
+    }
+
+    /// Remove keyword `name` given separately, ignoring ASCII case. Return
+    /// the removed keyword if found.
+    pub fn remove_keyword(&mut self, name: &str) -> Option<Keyword> {
+        let i = self
+            .items
+            .iter()
+            .position(|item| matches!(item, RouteItem::Keyword(kw) if kw.is(name)))?;
+        match self.items.remove(i) {
+            RouteItem::Keyword(kw) => Some(kw),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Iterate over all internal options in route.
+    pub fn iops(&self) -> impl Iterator<Item = &IOp> {
+        self.items.iter().flat_map(|item| match item {
+            RouteItem::IOp(iops) => iops.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Merge `iops` with the internal options in route. All IOps will be put
+    /// in a single group placed at the position of the first group. Return
+    /// error if the same option has been set to a different value.
+    pub fn merge_iops(&mut self, iops: &[IOp]) -> Result<()> {
+        let mut merged: Vec<IOp> = vec![];
+        for &iop in self.iops().chain(iops) {
+            match merged
+                .iter()
+                .find(|x| x.overlay == iop.overlay && x.option == iop.option)
+            {
+                Some(x) if x.value != iop.value => bail!("conflicting IOp found in route: {x} vs {iop}"),
+                Some(_) => {}
+                None => merged.push(iop),
+            }
+        }
+
+        let i = self.items.iter().position(|item| matches!(item, RouteItem::IOp(_)));
+        self.items.retain(|item| !matches!(item, RouteItem::IOp(_)));
+        let i = i.unwrap_or(self.items.len());
+        self.items.insert(i, RouteItem::IOp(merged));
+        Ok(())
+    }
+
+    /// Return true if symmetry is turned off by `NoSymm` or `Symmetry=None`.
+    pub fn no_symmetry(&self) -> bool {
+        self.keywords().any(|kw| {
+            let name = kw.name.to_lowercase();
+            name.starts_with("nosymm") || name.starts_with("symm") && kw.has_option("none")
+        })
+    }
+
+    /// Return true if additional overlay cards are requested by
+    /// `ExtraOverlay`.
+    pub fn extra_overlay(&self) -> bool {
+        self.has_keyword("extraoverlay")
+    }
+
+    /// Return true if title and molecule specification are read from
+    /// checkpoint file by `Geom=AllCheck`, which are absent in input.
+    pub fn geom_allcheck(&self) -> bool {
+        self.keywords()
+            .any(|kw| kw.name.to_lowercase().starts_with("geom") && kw.has_option("allcheck"))
+    }
+
+    /// Turn off symmetry. Return error if symmetry option has been set.
+    pub fn require_no_symmetry(&mut self) -> Result<()> {
+        if !self.no_symmetry() {
+            if let Some(kw) = self.keywords().find(|kw| kw.name.to_lowercase().starts_with("symm")) {
+                bail!("conflicting symmetry keyword found in route: {kw}");
+            }
+            self.require_keyword("nosymm");
+        }
+        Ok(())
+    }
+}
+// e3615c4e ends here
+
+// [[file:../../xo-tools.note::6acf1dee][6acf1dee]]
+#[test]
+fn test_route_keywords() -> Result<()> {
+    let route: Route = "#P XYG3(FC)/6-311+G(3df,2p) scf = (tight, xqc) SCRF(PCM,Solvent=Water) 6-31g(d,p)".parse()?;
+    assert_eq!(route.print_level, Some('P'));
+    assert_eq!(route.items.len(), 4);
+    match &route.items[0] {
+        RouteItem::MethodBasis(method, basis) => {
+            assert!(method.is("xyg3"));
+            assert!(method.has_option("FC"));
+            assert_eq!(basis, "6-311+G(3df,2p)");
+        }
+        _ => panic!("invalid method/basis pair"),
+    }
+    assert_eq!(route.find_keyword("SCF").unwrap().options, ["tight", "xqc"]);
+    assert_eq!(
+        route.to_string(),
+        "#P XYG3(FC)/6-311+G(3df,2p) scf=(tight,xqc) SCRF(PCM,Solvent=Water) 6-31g(d,p)"
+    );
+
+    let route: Route = "# b3lyp/gen NoSymmetry".parse()?;
+    assert_eq!(route.print_level, None);
+    assert!(route.no_symmetry());
+    let route: Route = "# b3lyp/gen Symmetry=None".parse()?;
+    assert!(route.no_symmetry());
+    let mut route: Route = "# b3lyp/gen Symmetry=Loose".parse()?;
+    assert!(!route.no_symmetry());
+    assert!(route.require_no_symmetry().is_err());
+
+    let mut route: Route = "# b3lyp/gen Opt=(Tight,CalcFC) freq".parse()?;
+    assert_eq!(route.remove_keyword("opt").unwrap().options, ["Tight", "CalcFC"]);
+    assert!(route.remove_keyword("opt").is_none());
+    assert!(route.remove_keyword("b3lyp").is_none());
+    assert_eq!(route.to_string(), "# b3lyp/gen freq");
+
+    let e = "# b3lyp/gen scf=(tight".parse::<Route>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let e = "# b3lyp/gen iop(5/33)".parse::<Route>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_route_iops() -> Result<()> {
+    // IOps wrapped over several route lines
+    let mut route: Route = "#p b3lyp IOp(5/ 33=1) nosymm iop(3/76=1000007400,\n 3/77=0999900001)".parse()?;
+    assert_eq!(route.iops().count(), 3);
+    route.merge_iops(&[IOp::new(5, 33, 1)])?;
+    assert_eq!(
+        route.to_string(),
+        "#P b3lyp iop(5/33=1,3/76=1000007400,3/77=999900001) nosymm"
+    );
+    assert!(route.merge_iops(&[IOp::new(5, 33, 2)]).is_err());
+
+    let mut route: Route = "# b3lyp".parse()?;
+    route.merge_iops(&[IOp::new(5, 33, 1)])?;
+    assert_eq!(route.to_string(), "# b3lyp iop(5/33=1)");
+
+    Ok(())
+}
+// 6acf1dee ends here
@@ -1,4 +1,28 @@
 // [[file:../xo-tools.note::95fd6309][95fd6309]]
+//! Tools for Gaussian calculations, usable as a library in other Rust tools
+//! without shelling out to the binaries:
+//!
+//! - [`input`]: typed Gaussian input and route section
+//! - [`logfile`]: parsers for Gaussian log files
+//! - [`xdh`]: rewriting input and collecting results for XYG3 type doubly
+//!   hybrid (xDH) functionals
+//! - [`runner`]: running Gaussian program
+//! - [`error`](mod@error): typed errors for failure kinds
+//!
+//! Public functions return [`Result`] with [`Error`], both re-exported from
+//! `anyhow`, in which the root cause is a [`GaussianError`] if the failure
+//! kind is known.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gaussian_tools::xdh::{xDH, Functional};
+//! use std::path::Path;
+//!
+//! let xdh = xDH::collect_from_gaussian(Path::new("o2.log"))?;
+//! println!("E(XYG3) = {:.8}", xdh.energy(Functional::XYG3));
+//! # Ok::<(), gaussian_tools::Error>(())
+//! ```
 #[allow(dead_code)]
 
 use gut::prelude::*;
@@ -7,18 +31,133 @@ use std::path::{Path, PathBuf};
 // 95fd6309 ends here
 
 // [[file:../xo-tools.note::218d7576][218d7576]]
-mod xdh;
-
 pub mod cli;
+pub mod error;
+pub mod input;
+pub mod logfile;
+pub mod runner;
+pub mod xdh;
+
+mod tail;
+
+pub use anyhow::{Error, Result};
+pub use error::GaussianError;
 // 218d7576 ends here
 
 // [[file:../xo-tools.note::d4c45061][d4c45061]]
+/// Compression formats of Gaussian logs in archives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// The number of leading bytes to detect all compression formats
+    const MAGIC_LEN: usize = 6;
+
+    /// Detect compression format from leading bytes of a stream.
+    fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Guess compression format from file extension.
+    fn from_extension(f: &Path) -> Option<Self> {
+        match f.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "xz" => Some(Self::Xz),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Wrap `r` in a decompressing reader if it is compressed as detected from
+/// magic bytes, or as indicated by `hint` if the stream is too short for
+/// magic bytes.
+fn decompress_reader<R: BufRead + 'static>(mut r: R, hint: Option<Compression>) -> Result<Box<dyn BufRead>> {
+    // a pipe may return less bytes than required in a single read
+    let mut magic = vec![];
+    (&mut r)
+        .take(Compression::MAGIC_LEN as u64)
+        .read_to_end(&mut magic)
+        .context("Could not read stream header")?;
+    let compression = match Compression::from_magic(&magic) {
+        None if magic.len() < Compression::MAGIC_LEN => hint,
+        x => x,
+    };
+    let r = std::io::Cursor::new(magic).chain(r);
+    let reader: Box<dyn BufRead> = match compression {
+        Some(Compression::Gzip) => Box::new(std::io::BufReader::new(flate2::bufread::MultiGzDecoder::new(r))),
+        Some(Compression::Xz) => Box::new(std::io::BufReader::new(xz2::bufread::XzDecoder::new_multi_decoder(r))),
+        Some(Compression::Zstd) => Box::new(std::io::BufReader::new(zstd::stream::read::Decoder::with_buffer(r)?)),
+        None => Box::new(r),
+    };
+    Ok(reader)
+}
+
 // re-exports
-fn file_reader<P: AsRef<Path>>(f: P) -> Result<impl BufRead> {
+/// Open file `f` for reading lines. Files compressed with gzip, xz or zstd
+/// are decompressed transparently.
+fn file_reader<P: AsRef<Path>>(f: P) -> Result<Box<dyn BufRead>> {
     let f = f.as_ref();
-    let f = std::fs::File::open(f).with_context(|| format!("Could not read file {:?}", f))?;
+    let r = std::fs::File::open(f).map_err(|source| error::GaussianError::IoError {
+        path: f.to_owned(),
+        source,
+    })?;
+
+    let r = std::io::BufReader::new(r);
+    decompress_reader(r, Compression::from_extension(f)).with_context(|| format!("Could not read file {:?}", f))
+}
 
-    let f = std::io::BufReader::new(f);
-    Ok(f)
+/// Read lines from stdin, which is decompressed transparently as in
+/// `file_reader`.
+fn stdin_reader() -> Result<Box<dyn BufRead>> {
+    decompress_reader(std::io::stdin().lock(), None)
 }
 // d4c45061 ends here
+
+// [[file:../xo-tools.note::8c41d7a2][8c41d7a2]]
+#[test]
+fn test_file_reader_compressed() -> Result<()> {
+    let plain = gut::fs::read_file("tests/files/Job_o2.log")?;
+    // the last one is zstd compressed without extension
+    for f in ["Job_o2.log.gz", "Job_o2.log.xz", "Job_o2.log.zst", "Job_o2.out"] {
+        let mut s = String::new();
+        file_reader(format!("tests/files/compressed/{f}"))?.read_to_string(&mut s)?;
+        assert_eq!(s, plain, "{f}");
+    }
+
+    let gz = std::fs::read("tests/files/compressed/Job_o2.log.gz")?;
+    assert_eq!(Compression::from_magic(&gz), Some(Compression::Gzip));
+    assert_eq!(Compression::from_magic(plain.as_bytes()), None);
+    let r = decompress_reader(std::io::Cursor::new(gz), None)?;
+    assert_eq!(r.lines().count(), plain.lines().count());
+
+    // a pipe returning one byte in a single read
+    let xz = std::fs::read("tests/files/compressed/Job_o2.log.xz")?;
+    let pipe = std::io::BufReader::with_capacity(1, std::io::Cursor::new(xz));
+    let mut s = String::new();
+    decompress_reader(pipe, None)?.read_to_string(&mut s)?;
+    assert_eq!(s, plain);
+
+    // plain text file with misleading extension
+    let tdir = tempfile::tempdir()?;
+    let f = tdir.path().join("Job_o2.log.gz");
+    std::fs::write(&f, &plain)?;
+    let mut s = String::new();
+    file_reader(&f)?.read_to_string(&mut s)?;
+    assert_eq!(s, plain);
+
+    Ok(())
+}
+// 8c41d7a2 ends here
@@ -0,0 +1,152 @@
+// [[file:../xo-tools.note::7e1d4a96][7e1d4a96]]
+//! Parsers for Gaussian log (output) files
+
+use super::*;
+use crate::error::GaussianError;
+// 7e1d4a96 ends here
+
+// [[file:../xo-tools.note::1c08ebe1][1c08ebe1]]
+mod chart;
+mod diagnosis;
+mod follow;
+mod optimization;
+mod summary;
+
+pub use chart::sparkline;
+pub use diagnosis::{Failure, FailureKind};
+pub use optimization::OptimizationStep;
+pub use summary::{ConvergenceItem, LogParser, LogSummary, Step, Termination, Thermochemistry};
+// 1c08ebe1 ends here
+
+// [[file:../xo-tools.note::*log][log:1]]
+/// Parse xyz coordinates of the last geometry from gaussian log file.
+pub fn parse_gaussian_log_file<P: AsRef<Path>>(path: P) -> Result<Vec<[f64; 3]>> {
+    let path = path.as_ref();
+    let parse_error = |line: usize, message: String| GaussianError::ParseError {
+        file: path.to_owned(),
+        line,
+        message,
+    };
+    let reader = file_reader(path)?;
+
+    // the keyword indicating lines for coordinates information
+    let key = " orientation:";
+
+    // record line number containing `orientation` line.
+    let mut line_numbers = vec![];
+
+    // record number of total atoms
+    let mut natoms = None;
+
+    // record current line number
+    let mut nline = 0;
+    for line in reader.lines() {
+        let line = line?;
+        if natoms.is_none() {
+            // Sample line:
+            // ------------
+            //  NAtoms=     60 NQM=       60 NQMF=       0 NMMI=      0 NMMIF=      0
+            //
+            let k = " NAtoms=";
+            if let Some(rest) = line.strip_prefix(k) {
+                let n = rest.split_whitespace().next().unwrap_or_default();
+                let n: usize = n
+                    .parse()
+                    .map_err(|_| parse_error(nline + 1, format!("invalid number of atoms: {line:?}")))?;
+                natoms = Some(n);
+            }
+        }
+
+        if line.contains(key) {
+            line_numbers.push(nline);
+        }
+        nline += 1;
+    }
+
+    // save the total number of lines.
+    let nlines = nline;
+
+    let natoms = natoms.ok_or_else(|| parse_error(nlines, "Failed to get total number of atoms.".into()))?;
+    // take the last good record of coordinates during multiple-step optimization.
+    let nstart = match line_numbers[..] {
+        [] => return Err(parse_error(nlines, "incomplete coordinates records".into()).into()),
+        [x] => x,
+        [.., x0, x] => {
+            if natoms + x < nlines {
+                x0
+            } else {
+                x
+            }
+        }
+    };
+
+    info!("Start line number of coordinates: {}", nstart);
+    let reader = file_reader(path)?;
+
+    // Sample record
+    //
+    // Standard orientation:
+    // ---------------------------------------------------------------------
+    //     Center     Atomic      Atomic             Coordinates (Angstroms)
+    //     Number     Number       Type             X           Y           Z
+    //     ---------------------------------------------------------------------
+    //     1          6           0        1.196105    0.388638    3.377408
+    //     2          6           0        0.739233   -1.017467    3.377408
+
+    let mut coords: Vec<_> = vec![];
+    for (line, i) in reader.lines().skip(nstart + 5).zip(0..natoms) {
+        let line = line?;
+        let nline = nstart + 5 + i + 1;
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let xyz = match parts[..] {
+            [_, _, _, x, y, z, ..] => [x, y, z].map(|x| x.parse::<f64>()),
+            _ => return Err(parse_error(nline, format!("missing coordinates: {line:?}")).into()),
+        };
+        match xyz {
+            [Ok(x), Ok(y), Ok(z)] => coords.push([x, y, z]),
+            _ => return Err(parse_error(nline, format!("invalid coordinates: {line:?}")).into()),
+        }
+    }
+    if coords.len() != natoms {
+        let message = format!("incomplete coordinates record: {} of {natoms} atoms", coords.len());
+        return Err(parse_error(nlines, message).into());
+    }
+
+    Ok(coords)
+}
+
+#[test]
+#[ignore]
+fn test_parse_log() -> Result<()> {
+    let fname = "/share/apps/gaussian/g09/tests/amd64/test0333.log";
+    let coords = parse_gaussian_log_file(fname)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_log_errors() -> Result<()> {
+    let f = "tests/files/Job_o2.log";
+    let coords = parse_gaussian_log_file(f)?;
+    assert_eq!(coords, [[-3.339026, -1.011956, 0.0], [-4.500626, -1.011956, 0.0]]);
+
+    // truncated in the middle of coordinates table
+    let s = gut::fs::read_file(f)?;
+    let tdir = tempfile::tempdir()?;
+    let truncated = tdir.path().join("truncated.log");
+    gut::fs::write_to_file(&truncated, &s.lines().take(134).join("\n"))?;
+    let e = parse_gaussian_log_file(&truncated).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::ParseError { line: 134, .. })
+    ));
+
+    let e = parse_gaussian_log_file("tests/files/Test001.gjf").unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::ParseError { .. })
+    ));
+
+    Ok(())
+}
+// log:1 ends here
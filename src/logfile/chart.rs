@@ -0,0 +1,111 @@
+// [[file:../../xo-tools.note::b58e2c07][b58e2c07]]
+use super::*;
+// b58e2c07 ends here
+
+// [[file:../../xo-tools.note::0ad3f971][0ad3f971]]
+/// Unicode block characters for sparklines from low to high
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Plain ASCII characters for terminals without Unicode support or
+/// redirected output
+const ASCII_BARS: [char; 8] = ['_', '.', '-', '~', '=', '+', '*', '#'];
+
+/// The maximum number of columns in a sparkline. Long series are resampled
+/// to fit in.
+const MAX_WIDTH: usize = 64;
+
+/// Pick at most `width` evenly spaced values in `values`, keeping the first
+/// and the last ones.
+fn resample(values: &[f64], width: usize) -> Vec<f64> {
+    let n = values.len();
+    if n <= width || width < 2 {
+        return values.to_vec();
+    }
+    (0..width).map(|i| values[i * (n - 1) / (width - 1)]).collect()
+}
+
+/// Draw `values` in a sparkline using Unicode block characters, or ASCII
+/// characters if `unicode` is false. Non-finite values are drawn as spaces.
+pub fn sparkline(values: &[f64], unicode: bool) -> String {
+    let bars = if unicode { BARS } else { ASCII_BARS };
+    let values = resample(values, MAX_WIDTH);
+    let finite = values.iter().copied().filter(|x| x.is_finite());
+    let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), x| (a.min(x), b.max(x)));
+    values
+        .iter()
+        .map(|&x| {
+            if !x.is_finite() {
+                ' '
+            } else if max > min {
+                let i = ((x - min) / (max - min) * (bars.len() - 1) as f64).round() as usize;
+                bars[i]
+            } else {
+                bars[0]
+            }
+        })
+        .collect()
+}
+
+impl LogSummary {
+    /// Draw SCF energy and max force of geometry optimization steps in
+    /// sparklines, using Unicode block characters if `unicode` is true.
+    /// The max force is drawn in log scale.
+    pub fn optimization_chart(&self, unicode: bool) -> String {
+        let rows = self.optimization_steps();
+        if rows.is_empty() {
+            return " no geometry optimization steps found\n".into();
+        }
+        let energies: Vec<_> = rows.iter().map(|x| x.energy.unwrap_or(f64::NAN)).collect();
+        let forces: Vec<_> = rows
+            .iter()
+            .map(|x| x.max_force.as_ref().map_or(f64::NAN, |x| x.value))
+            .collect();
+        let log_forces: Vec<_> = forces.iter().map(|x| x.log10()).collect();
+        let first_last = |values: &[f64], precision: usize| {
+            let first = values.first().copied().unwrap_or(f64::NAN);
+            let last = values.last().copied().unwrap_or(f64::NAN);
+            format!("{first:.precision$} -> {last:.precision$}")
+        };
+
+        let mut txt = format!(" {} steps\n", rows.len());
+        txt.push_str(&format!(
+            " {:<12} {}  {}\n",
+            "SCF energy",
+            sparkline(&energies, unicode),
+            first_last(&energies, 10)
+        ));
+        txt.push_str(&format!(
+            " {:<12} {}  {}\n",
+            "max force",
+            sparkline(&log_forces, unicode),
+            first_last(&forces, 6)
+        ));
+        txt
+    }
+}
+// 0ad3f971 ends here
+
+// [[file:../../xo-tools.note::71e4cb38][71e4cb38]]
+#[test]
+fn test_sparkline() -> Result<()> {
+    assert_eq!(sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], true), "▁▂▃▄▅▆▇█");
+    assert_eq!(sparkline(&[3.0, 1.0, f64::NAN, 2.0], false), "#_ =");
+    assert_eq!(sparkline(&[1.0, 1.0], true), "▁▁");
+    assert_eq!(sparkline(&[], true), "");
+    let values: Vec<_> = (0..200).map(|x| x as f64).collect();
+    let line = sparkline(&values, true);
+    assert_eq!(line.chars().count(), MAX_WIDTH);
+    assert!(line.starts_with('▁') && line.ends_with('█'));
+
+    let summary = LogSummary::from_file("tests/files/logs/h2o-opt-freq.log".as_ref())?;
+    let chart = summary.optimization_chart(false);
+    let lines: Vec<_> = chart.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].contains("#__  -76.4085432117 -> -76.4089257318"));
+    assert!(lines[2].contains("#=_  0.013427 -> 0.000041"));
+    assert!(chart.is_ascii());
+    let summary = LogSummary::from_file("tests/files/Job_o2.log".as_ref())?;
+    assert_eq!(summary.optimization_chart(true).lines().count(), 1);
+
+    Ok(())
+}
+// 71e4cb38 ends here
@@ -0,0 +1,264 @@
+// [[file:../../xo-tools.note::c3a9e5f1][c3a9e5f1]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+// c3a9e5f1 ends here
+
+// [[file:../../xo-tools.note::7b2f0d64][7b2f0d64]]
+/// Common kinds of Gaussian failure recognized from error messages in log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureKind {
+    /// Geometry optimization not converged within maximum steps, reported
+    /// by l9999
+    OptimizationNotConverged,
+    /// SCF not converged within maximum cycles in l502
+    ScfNotConverged,
+    /// Failed to build internal coordinates: "FormBX had a problem"
+    FormBX,
+    /// "Linear angle in Bend" or "Linear angle in Tors" in internal
+    /// coordinates
+    LinearAngle,
+    /// galloc: could not allocate memory
+    OutOfMemory,
+    /// "Erroneous write", usually for lack of disk space
+    ErroneousWrite,
+    /// Error termination without a known signature
+    Unknown,
+}
+
+impl FailureKind {
+    /// Recognize failure signature in a trimmed line of Gaussian log.
+    fn detect(line: &str) -> Option<Self> {
+        let kind = if line.contains("Number of steps exceeded") {
+            Self::OptimizationNotConverged
+        } else if line.starts_with("Convergence failure -- run terminated") {
+            Self::ScfNotConverged
+        } else if line.starts_with("FormBX had a problem") {
+            Self::FormBX
+        } else if line.starts_with("Linear angle in Bend") || line.starts_with("Linear angle in Tors") {
+            Self::LinearAngle
+        } else if line.starts_with("galloc:") {
+            Self::OutOfMemory
+        } else if line.starts_with("Erroneous write") {
+            Self::ErroneousWrite
+        } else {
+            return None;
+        };
+        Some(kind)
+    }
+
+    /// Guess the failure from the link of error termination only.
+    fn from_link(link: Option<&str>) -> Self {
+        match link {
+            Some("l9999") => Self::OptimizationNotConverged,
+            Some("l502") => Self::ScfNotConverged,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// A short description of the failure
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::OptimizationNotConverged => "geometry optimization not converged",
+            Self::ScfNotConverged => "SCF not converged",
+            Self::FormBX => "failed to build internal coordinates (FormBX)",
+            Self::LinearAngle => "linear angle in internal coordinates",
+            Self::OutOfMemory => "failed to allocate memory",
+            Self::ErroneousWrite => "failed to write scratch files",
+            Self::Unknown => "unrecognized error",
+        }
+    }
+
+    /// The suggested change to route section or job setup
+    pub fn hint(&self) -> Option<&'static str> {
+        let hint = match self {
+            Self::OptimizationNotConverged => {
+                "restart from the last geometry with Opt=(MaxCycles=N) Geom=AllCheck Guess=Read, or use Opt=CalcFC"
+            }
+            Self::ScfNotConverged => "use SCF=XQC or SCF=(QC,MaxCycles=N), or read a better guess with Guess=Read",
+            Self::FormBX => "use Opt=Cartesian, or restart from the last geometry with Geom=AllCheck",
+            Self::LinearAngle => "use Opt=Cartesian, or add dummy atoms to the linear fragment",
+            Self::OutOfMemory => "decrease %Mem below the memory available on the node",
+            Self::ErroneousWrite => "free disk space in GAUSS_SCRDIR, or limit scratch files with MaxDisk",
+            Self::Unknown => return None,
+        };
+        Some(hint)
+    }
+}
+
+/// The classified failure of Gaussian job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Failure {
+    pub kind: FailureKind,
+    /// The last link entered or terminated with error, such as "l502"
+    pub link: Option<String>,
+    /// The line of error message in Gaussian log
+    pub message: String,
+    /// The suggested change to route section or job setup
+    pub hint: Option<String>,
+}
+
+impl Failure {
+    fn new(kind: FailureKind, link: Option<String>, message: &str) -> Self {
+        Self {
+            kind,
+            link,
+            message: message.to_owned(),
+            hint: kind.hint().map(|x| x.to_owned()),
+        }
+    }
+
+    /// Classify the failure from a trimmed line of Gaussian log, with the
+    /// last link entered.
+    pub(super) fn detect(line: &str, link: Option<&str>) -> Option<Self> {
+        let kind = FailureKind::detect(line)?;
+        Some(Self::new(kind, link.map(|x| x.to_owned()), line))
+    }
+
+    /// Classify the failure from error termination line only.
+    pub(super) fn from_termination(line: &str, link: Option<&str>) -> Self {
+        let kind = FailureKind::from_link(link);
+        Self::new(kind, link.map(|x| x.to_owned()), line)
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.kind.description())?;
+        if let Some(link) = &self.link {
+            write!(f, " in {link}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Parse the link in Gaussian log line, such as "l502" in:
+///
+/// ```text
+///  (Enter /share/apps/gaussian/g16/l502.exe)
+///  Error termination via Lnk1e in /share/apps/gaussian/g16/l502.exe at Fri Mar  3 10:00:04 2023.
+///  Error termination request processed by link 9999.
+/// ```
+pub(super) fn parse_link(line: &str) -> Option<String> {
+    if let Some(s) = line.strip_prefix("Error termination request processed by link") {
+        let n: usize = s.trim().trim_end_matches('.').parse().ok()?;
+        return Some(format!("l{n}"));
+    }
+    if !(line.starts_with("(Enter ") || line.starts_with("Error termination via")) {
+        return None;
+    }
+    let exe = line
+        .split_whitespace()
+        .find_map(|x| x.trim_end_matches(')').strip_suffix(".exe"))?;
+    let link = exe.rsplit('/').next()?;
+    link.starts_with('l').then(|| link.to_owned())
+}
+
+impl LogSummary {
+    /// Fail if Gaussian in log file `f` does not terminate normally, with
+    /// the termination message and the classified failure if any.
+    pub fn check_termination(&self, f: &Path) -> Result<()> {
+        match &self.termination {
+            Some(t) if t.normal => Ok(()),
+            t => {
+                let mut tail = vec![];
+                match t {
+                    Some(t) => tail.push(t.message.clone()),
+                    None if self.failure.is_none() => tail.push("no termination found".to_owned()),
+                    None => {}
+                }
+                if let Some(failure) = &self.failure {
+                    tail.push(format!("{failure}"));
+                    if let Some(hint) = &failure.hint {
+                        tail.push(format!("hint: {hint}"));
+                    }
+                }
+                let e = GaussianError::GaussianTerminatedAbnormally {
+                    output: f.to_owned(),
+                    tail: tail.join("\n"),
+                };
+                Err(e.into())
+            }
+        }
+    }
+}
+// 7b2f0d64 ends here
+
+// [[file:../../xo-tools.note::e85d1a37][e85d1a37]]
+#[test]
+fn test_log_failure() -> Result<()> {
+    assert_eq!(
+        parse_link("(Enter /share/apps/gaussian/g16/l502.exe)").as_deref(),
+        Some("l502")
+    );
+    let line = "Error termination via Lnk1e in /g16/l9999.exe at Fri Mar  3 10:00:02 2023.";
+    assert_eq!(parse_link(line).as_deref(), Some("l9999"));
+    let line = "Error termination request processed by link 9999.";
+    assert_eq!(parse_link(line).as_deref(), Some("l9999"));
+    assert_eq!(parse_link("Normal termination of Gaussian 16"), None);
+
+    let f: &Path = "tests/files/logs/h2o-scf-failure.log".as_ref();
+    let mut parser = LogParser::default();
+    parser.feed(gut::fs::read_file(f)?.as_bytes());
+    // job timing follows error termination
+    assert!(parser.is_terminated());
+    let summary = parser.finish();
+    assert_eq!(summary, LogSummary::from_file(f)?);
+    assert!(!summary.termination.as_ref().unwrap().normal);
+    let failure = summary.failure.as_ref().unwrap();
+    assert_eq!(failure.kind, FailureKind::ScfNotConverged);
+    assert_eq!(failure.link.as_deref(), Some("l502"));
+    assert_eq!(failure.message, "Convergence failure -- run terminated.");
+    assert!(failure.hint.as_ref().unwrap().contains("SCF=XQC"));
+    let txt = summary.to_text();
+    assert!(txt.contains(" Failure: SCF not converged in l502: Convergence failure -- run terminated.\n"));
+    assert!(txt.contains(" Hint: use SCF=XQC"));
+    let summary_json: LogSummary = serde_json::from_str(&summary.to_json()?)?;
+    assert_eq!(summary_json, summary);
+
+    let f: &Path = "tests/files/logs/h2o-opt-maxcycle.log".as_ref();
+    let summary = LogSummary::from_file(f)?;
+    let failure = summary.failure.as_ref().unwrap();
+    assert_eq!(failure.kind, FailureKind::OptimizationNotConverged);
+    assert_eq!(failure.link.as_deref(), Some("l9999"));
+    assert!(failure.hint.as_ref().unwrap().contains("Geom=AllCheck"));
+    let e = summary.check_termination(f).unwrap_err();
+    assert!(format!("{e}").contains("geometry optimization not converged in l9999"));
+
+    // no failure in normal termination
+    let summary = LogSummary::from_file("tests/files/logs/h2o-opt-freq.log".as_ref())?;
+    assert_eq!(summary.failure, None);
+
+    // other signatures in the tail of log
+    let cases = [
+        ("FormBX had a problem.", "l103", FailureKind::FormBX),
+        ("Linear angle in Bend.", "l103", FailureKind::LinearAngle),
+        (
+            "galloc:  could not allocate memory.: Resource temporarily unavailable",
+            "l1",
+            FailureKind::OutOfMemory,
+        ),
+        (
+            "Erroneous write. Write -1 instead of 4096.",
+            "l502",
+            FailureKind::ErroneousWrite,
+        ),
+        (
+            "Error termination via Lnk1e in /g16/l301.exe at Fri Mar  3 10:00:02 2023.",
+            "l301",
+            FailureKind::Unknown,
+        ),
+    ];
+    for (line, link, kind) in cases {
+        let mut parser = LogParser::default();
+        parser.feed_line(&format!(" (Enter /g16/{link}.exe)"));
+        parser.feed_line(&format!(" {line}"));
+        let summary = parser.finish();
+        let failure = summary.failure.as_ref().unwrap();
+        assert_eq!(failure.kind, kind);
+        assert_eq!(failure.link.as_deref(), Some(link));
+    }
+
+    Ok(())
+}
+// e85d1a37 ends here
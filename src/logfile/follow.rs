@@ -0,0 +1,140 @@
+// [[file:../../xo-tools.note::e26a4f8d][e26a4f8d]]
+use super::*;
+
+use std::time::Duration;
+// e26a4f8d ends here
+
+// [[file:../../xo-tools.note::95c3d0b1][95c3d0b1]]
+impl crate::tail::Tail for LogParser {
+    fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        LogParser::feed(self, chunk);
+        Ok(())
+    }
+
+    fn is_terminated(&self) -> bool {
+        LogParser::is_terminated(self)
+    }
+}
+
+impl LogSummary {
+    /// Follow Gaussian log file `f` while being written, like `tail -f`,
+    /// until Gaussian terminates without further job step. The `progress`
+    /// callback is called with the parser after reading new data.
+    ///
+    /// If the log is not written for `timeout`, as for a job killed without
+    /// termination, the summary of lines written so far is returned.
+    pub fn follow(
+        f: &Path,
+        interval: Duration,
+        timeout: Option<Duration>,
+        progress: impl FnMut(&LogParser),
+    ) -> Result<Self> {
+        let file = crate::tail::open(f)?;
+        let mut parser = LogParser::default();
+        if !crate::tail::follow(file, &mut parser, interval, timeout, progress)? {
+            warn!("{f:?} not written in {timeout:?}, Gaussian may be killed");
+        }
+        Ok(parser.finish())
+    }
+}
+// 95c3d0b1 ends here
+
+// [[file:../../xo-tools.note::4f70b8ea][4f70b8ea]]
+#[test]
+fn test_log_follow() -> Result<()> {
+    use std::time::Duration;
+
+    let f: &Path = "tests/files/logs/h2o-opt-freq.log".as_ref();
+    let s = gut::fs::read_file(f)?;
+    let expected = LogSummary::from_file(f)?;
+
+    // feed in chunks splitting lines
+    let mut parser = LogParser::default();
+    for chunk in s.as_bytes().chunks(100) {
+        parser.feed(chunk);
+    }
+    assert!(parser.is_terminated());
+    assert!(parser.status().contains("Normal termination of Gaussian 16"));
+    assert_eq!(parser.finish(), expected);
+
+    let mut parser = LogParser::default();
+    // in the middle of SCF for the third geometry
+    for line in s.lines().take(194) {
+        parser.feed_line(line);
+    }
+    assert_eq!(
+        parser.status(),
+        "step 2, SCF cycle 2, E(RB3LYP) = -76.4089205632, converged: NO NO NO NO"
+    );
+    assert!(!parser.is_terminated());
+
+    // follow the log being written, with no new data at `None`
+    struct Script(std::collections::VecDeque<Option<Vec<u8>>>);
+    impl Read for Script {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.pop_front() {
+                Some(Some(chunk)) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+    let script = |s: &str, idle: usize| {
+        let mut chunks: std::collections::VecDeque<_> = s.as_bytes().chunks(1000).map(|x| Some(x.to_vec())).collect();
+        // no new data for a while in the first geometry
+        for _ in 0..idle {
+            chunks.insert(1, None);
+        }
+        Script(chunks)
+    };
+    let interval = Duration::from_millis(1);
+    let mut parser = LogParser::default();
+    let mut updates = 0;
+    let r = script(&s, 3);
+    let n = r.0.iter().flatten().count();
+    let finished = crate::tail::follow(r, &mut parser, interval, None, |_| updates += 1)?;
+    assert!(finished);
+    assert_eq!(updates, n);
+    assert_eq!(parser.finish(), expected);
+
+    // killed without termination in the second job step
+    let killed = s.lines().take(230).join("\n");
+    let mut parser = LogParser::default();
+    let timeout = Some(Duration::from_millis(5));
+    let finished = crate::tail::follow(script(&killed, 3), &mut parser, interval, timeout, |_| {})?;
+    assert!(!finished);
+    let summary = parser.finish();
+    assert_eq!(summary.termination, None);
+    let e = summary.check_termination(f).unwrap_err();
+    assert!(e.to_string().contains("no termination found"));
+    assert_eq!(crate::error::exit_code(&e), 5);
+
+    // the log file written completely or partially
+    let tdir = tempfile::tempdir()?;
+    let g = tdir.path().join("job.log");
+    std::fs::write(&g, &s)?;
+    let summary = LogSummary::follow(&g, interval, None, |_| {})?;
+    assert_eq!(summary, expected);
+    summary.check_termination(&g)?;
+    std::fs::write(&g, &killed)?;
+    let summary = LogSummary::follow(&g, interval, timeout, |_| {})?;
+    assert!(summary.check_termination(&g).is_err());
+
+    // error termination
+    let txt = format!(
+        "{}\n Error termination via Lnk1e in /g16/l9999.exe at Fri Mar  3 10:00:05 2023.\n",
+        &s[..2000]
+    );
+    std::fs::write(&g, txt)?;
+    let summary = LogSummary::follow(&g, interval, None, |_| {})?;
+    let e = summary.check_termination(&g).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::GaussianTerminatedAbnormally { .. })
+    ));
+
+    Ok(())
+}
+// 4f70b8ea ends here
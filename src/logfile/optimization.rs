@@ -0,0 +1,156 @@
+// [[file:../../xo-tools.note::8c31e0a6][8c31e0a6]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+// 8c31e0a6 ends here
+
+// [[file:../../xo-tools.note::f47b2d19][f47b2d19]]
+/// Convergence of a step in geometry optimization, for spotting oscillating
+/// optimizations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimizationStep {
+    /// The step number of geometry optimization
+    pub step: usize,
+    /// The SCF energy in Hartree
+    pub energy: Option<f64>,
+    /// The energy change from previous step in Hartree
+    pub delta_energy: Option<f64>,
+    pub max_force: Option<ConvergenceItem>,
+    pub rms_force: Option<ConvergenceItem>,
+    pub max_displacement: Option<ConvergenceItem>,
+    pub rms_displacement: Option<ConvergenceItem>,
+}
+
+impl OptimizationStep {
+    /// The criteria in the same order as in Gaussian log
+    fn criteria(&self) -> [Option<&ConvergenceItem>; 4] {
+        [
+            self.max_force.as_ref(),
+            self.rms_force.as_ref(),
+            self.max_displacement.as_ref(),
+            self.rms_displacement.as_ref(),
+        ]
+    }
+
+    /// Return true if all criteria are met.
+    pub fn is_converged(&self) -> bool {
+        self.criteria().iter().all(|x| x.is_some_and(|x| x.converged))
+    }
+}
+
+fn yes_or_no(converged: bool) -> &'static str {
+    if converged {
+        "YES"
+    } else {
+        "NO"
+    }
+}
+
+impl LogSummary {
+    /// Return convergence of all steps in geometry optimization.
+    pub fn optimization_steps(&self) -> Vec<OptimizationStep> {
+        let mut rows: Vec<OptimizationStep> = vec![];
+        for step in self.steps.iter().filter(|x| !x.convergence.is_empty()) {
+            let find = |name: &str| step.convergence.iter().find(|x| x.item == name).cloned();
+            let previous = rows.last().and_then(|x| x.energy);
+            rows.push(OptimizationStep {
+                step: step.number.unwrap_or(rows.len() + 1),
+                energy: step.scf_energy,
+                delta_energy: step.scf_energy.zip(previous).map(|(e, e0)| e - e0),
+                max_force: find("Maximum Force"),
+                rms_force: find("RMS Force"),
+                max_displacement: find("Maximum Displacement"),
+                rms_displacement: find("RMS Displacement"),
+            });
+        }
+        rows
+    }
+
+    /// Format convergence of geometry optimization in a human-readable
+    /// table, with thresholds in the last line.
+    pub fn optimization_text(&self) -> String {
+        let rows = self.optimization_steps();
+        let mut txt = format!(
+            " {:>4} {:>16} {:>12} {:>14} {:>14} {:>14} {:>14}\n",
+            "step", "energy", "dE", "max force", "rms force", "max disp", "rms disp"
+        );
+        for row in &rows {
+            let energy = row.energy.map(|x| format!("{x:16.10}")).unwrap_or_default();
+            let de = row.delta_energy.map(|x| format!("{x:12.8}")).unwrap_or_default();
+            txt.push_str(&format!(" {:>4} {energy:>16} {de:>12}", row.step));
+            for x in row.criteria() {
+                let s = x
+                    .map(|x| format!("{:.6} {:>3}", x.value, yes_or_no(x.converged)))
+                    .unwrap_or_default();
+                txt.push_str(&format!(" {s:>14}"));
+            }
+            txt.push('\n');
+        }
+        if let Some(row) = rows.last() {
+            let mut line = format!(" {:>4} {:>16} {:>12}", "", "", "threshold");
+            for x in row.criteria() {
+                let s = x.map(|x| format!("{:.6}    ", x.threshold)).unwrap_or_default();
+                line.push_str(&format!(" {s:>14}"));
+            }
+            txt.push_str(line.trim_end());
+            txt.push('\n');
+        }
+        txt
+    }
+
+    /// Format convergence of geometry optimization in CSV for plotting.
+    pub fn optimization_csv(&self) -> String {
+        let mut csv = "step,energy,delta_energy".to_owned();
+        for name in ["max_force", "rms_force", "max_displacement", "rms_displacement"] {
+            csv.push_str(&format!(",{name},{name}_threshold,{name}_converged"));
+        }
+        csv.push('\n');
+        for row in self.optimization_steps() {
+            let energy = row.energy.map(|x| x.to_string()).unwrap_or_default();
+            let de = row.delta_energy.map(|x| x.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{energy},{de}", row.step));
+            for x in row.criteria() {
+                match x {
+                    Some(x) => csv.push_str(&format!(",{},{},{}", x.value, x.threshold, yes_or_no(x.converged))),
+                    None => csv.push_str(",,,"),
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+// f47b2d19 ends here
+
+// [[file:../../xo-tools.note::3d6e9b52][3d6e9b52]]
+#[test]
+fn test_optimization_steps() -> Result<()> {
+    let summary = LogSummary::from_file("tests/files/logs/h2o-opt-freq.log".as_ref())?;
+    let rows = summary.optimization_steps();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows.iter().map(|x| x.step).collect_vec(), [1, 2, 3]);
+    assert_eq!(rows[0].delta_energy, None);
+    let de = rows[1].delta_energy.unwrap();
+    assert!((de - (-76.4089205632 + 76.4085432117)).abs() < 1e-12);
+    assert_eq!(rows[0].max_force.as_ref().unwrap().value, 0.013427);
+    assert_eq!(rows[1].rms_displacement.as_ref().unwrap().threshold, 0.0012);
+    assert!(!rows[1].is_converged());
+    assert!(rows[2].is_converged());
+
+    let txt = summary.optimization_text();
+    assert_eq!(txt.lines().count(), 1 + 3 + 1);
+    assert!(txt.lines().nth(3).unwrap().ends_with("0.000066 YES"));
+    let csv = summary.optimization_csv();
+    assert_eq!(csv.lines().count(), 1 + 3);
+    assert_eq!(csv.lines().next().unwrap().split(',').count(), 3 + 4 * 3);
+    let line = csv.lines().nth(1).unwrap();
+    assert!(line.starts_with("1,-76.4085432117,,0.013427,0.00045,NO,"));
+
+    // no optimization in single point calculation
+    let summary = LogSummary::from_file("tests/files/Job_o2.log".as_ref())?;
+    assert!(summary.optimization_steps().is_empty());
+    assert_eq!(summary.optimization_csv().lines().count(), 1);
+
+    Ok(())
+}
+// 3d6e9b52 ends here
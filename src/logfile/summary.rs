@@ -0,0 +1,616 @@
+// [[file:../../xo-tools.note::6a0f3d82][6a0f3d82]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+// 6a0f3d82 ends here
+
+// [[file:../../xo-tools.note::d1c47b95][d1c47b95]]
+/// A row in the convergence table of geometry optimization:
+///
+/// ```text
+///          Item               Value     Threshold  Converged?
+///  Maximum Force            0.013427     0.000450     NO
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvergenceItem {
+    pub item: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub converged: bool,
+}
+
+impl ConvergenceItem {
+    fn parse(line: &str) -> Option<Self> {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let [item @ .., value, threshold, converged] = &parts[..] else {
+            return None;
+        };
+        let converged = match *converged {
+            "YES" => true,
+            "NO" => false,
+            _ => return None,
+        };
+        if item.is_empty() {
+            return None;
+        }
+        Some(Self {
+            item: item.join(" "),
+            value: value.parse().ok()?,
+            threshold: threshold.parse().ok()?,
+            converged,
+        })
+    }
+}
+
+/// Results at a single geometry in Gaussian log, such as a step in
+/// geometry optimization.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// The step number of geometry optimization
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<usize>,
+    /// The method in SCF Done line, such as "RB3LYP"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scf_method: Option<String>,
+    /// The SCF energy in Hartree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scf_energy: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scf_cycles: Option<usize>,
+    /// The convergence table of geometry optimization
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub convergence: Vec<ConvergenceItem>,
+    /// Other notable lines, such as ONIOM or counterpoise energies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+//  SCF Done:  E(RB3LYP) =  -76.4085432117     A.U. after   10 cycles
+fn parse_scf_done(line: &str) -> Option<(String, f64, Option<usize>)> {
+    let s = line.trim_start().strip_prefix("SCF Done:")?;
+    let (label, rest) = s.split_once('=')?;
+    let method = label.trim().strip_prefix("E(")?.strip_suffix(')')?;
+    let parts: Vec<_> = rest.split_whitespace().collect();
+    let energy = parts.first()?.parse().ok()?;
+    let cycles = match parts[..] {
+        [.., n, "cycles"] => n.parse().ok(),
+        _ => None,
+    };
+    Some((method.to_owned(), energy, cycles))
+}
+
+/// Thermochemistry results in frequency calculation, in Hartree
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Thermochemistry {
+    pub zero_point_correction: Option<f64>,
+    pub thermal_correction_to_energy: Option<f64>,
+    pub thermal_correction_to_enthalpy: Option<f64>,
+    pub thermal_correction_to_free_energy: Option<f64>,
+    /// Sum of electronic and zero-point energies
+    pub zero_point_energy: Option<f64>,
+    /// Sum of electronic and thermal energies
+    pub thermal_energy: Option<f64>,
+    /// Sum of electronic and thermal enthalpies
+    pub enthalpy: Option<f64>,
+    /// Sum of electronic and thermal free energies
+    pub free_energy: Option<f64>,
+}
+
+impl Thermochemistry {
+    /// Labels in Gaussian log in the same order as `values`
+    const LABELS: [&'static str; 8] = [
+        "Zero-point correction",
+        "Thermal correction to Energy",
+        "Thermal correction to Enthalpy",
+        "Thermal correction to Gibbs Free Energy",
+        "Sum of electronic and zero-point Energies",
+        "Sum of electronic and thermal Energies",
+        "Sum of electronic and thermal Enthalpies",
+        "Sum of electronic and thermal Free Energies",
+    ];
+
+    fn values(&self) -> [Option<f64>; 8] {
+        [
+            self.zero_point_correction,
+            self.thermal_correction_to_energy,
+            self.thermal_correction_to_enthalpy,
+            self.thermal_correction_to_free_energy,
+            self.zero_point_energy,
+            self.thermal_energy,
+            self.enthalpy,
+            self.free_energy,
+        ]
+    }
+
+    fn values_mut(&mut self) -> [&mut Option<f64>; 8] {
+        [
+            &mut self.zero_point_correction,
+            &mut self.thermal_correction_to_energy,
+            &mut self.thermal_correction_to_enthalpy,
+            &mut self.thermal_correction_to_free_energy,
+            &mut self.zero_point_energy,
+            &mut self.thermal_energy,
+            &mut self.enthalpy,
+            &mut self.free_energy,
+        ]
+    }
+}
+
+//  Zero-point correction=                           0.021180 (Hartree/Particle)
+//  Sum of electronic and thermal Free Energies=          -76.405373
+fn parse_thermochemistry(line: &str) -> Option<(usize, f64)> {
+    let (label, value) = line.split_once('=')?;
+    let i = Thermochemistry::LABELS.iter().position(|&x| x == label.trim())?;
+    let value = value.split_whitespace().next()?.parse().ok()?;
+    Some((i, value))
+}
+
+/// The termination of Gaussian job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Termination {
+    pub normal: bool,
+    /// The termination line in Gaussian log
+    pub message: String,
+}
+
+//  Job cpu time:       0 days  0 hours  0 minutes  7.0 seconds.
+fn parse_cpu_time(line: &str) -> Option<f64> {
+    let s = line.trim_start().strip_prefix("Job cpu time:")?;
+    let parts: Vec<_> = s.split_whitespace().collect();
+    let mut seconds = 0.0;
+    for pair in parts.chunks(2) {
+        let [value, unit] = pair else {
+            return None;
+        };
+        let value: f64 = value.parse().ok()?;
+        let factor = match unit.trim_end_matches('.') {
+            "days" => 86400.0,
+            "hours" => 3600.0,
+            "minutes" => 60.0,
+            "seconds" => 1.0,
+            _ => return None,
+        };
+        seconds += value * factor;
+    }
+    Some(seconds)
+}
+
+/// Format `seconds` like Gaussian: "0 days  0 hours  0 minutes  7.0 seconds."
+fn format_cpu_time(seconds: f64) -> String {
+    let days = (seconds / 86400.0).floor();
+    let hours = ((seconds - days * 86400.0) / 3600.0).floor();
+    let minutes = ((seconds - days * 86400.0 - hours * 3600.0) / 60.0).floor();
+    let seconds = seconds - days * 86400.0 - hours * 3600.0 - minutes * 60.0;
+    format!("{days:>7} days {hours:>2} hours {minutes:>2} minutes {seconds:>4.1} seconds.")
+}
+
+/// The essential information in Gaussian log file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogSummary {
+    /// Gaussian program revision, such as "Gaussian 16, Revision A.03"
+    pub revision: Option<String>,
+    pub stoichiometry: Option<String>,
+    pub framework_group: Option<String>,
+    pub degrees_of_freedom: Option<usize>,
+    /// The basis set, such as "6-31G(d) (6D, 7F)"
+    pub basis: Option<String>,
+    pub basis_functions: Option<usize>,
+    /// Results at each geometry in the order of Gaussian log
+    pub steps: Vec<Step>,
+    /// Harmonic frequencies in cm**-1 of the last frequency calculation
+    pub frequencies: Vec<f64>,
+    pub thermochemistry: Option<Thermochemistry>,
+    /// The last termination of Gaussian, absent if Gaussian is running or
+    /// killed
+    pub termination: Option<Termination>,
+    /// The classified failure if Gaussian terminates with error or is
+    /// killed with a known error message
+    pub failure: Option<Failure>,
+    /// Total CPU time in seconds of all job steps
+    pub cpu_time: Option<f64>,
+    pub warnings: Vec<String>,
+}
+
+/// State of `LogParser` for lines following a header line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum State {
+    #[default]
+    Normal,
+    /// Rows of convergence table following "Converged?" line
+    Convergence,
+    /// Other lines of eigenvalues following the first "Eigenvalues ---" line
+    Eigenvalues,
+    /// The line following "ONIOM: Dipole moment"
+    DipoleMoment,
+}
+
+/// Incremental parser of Gaussian log, which consumes each line only once.
+/// Lines can be fed while Gaussian is still writing the log.
+#[derive(Debug, Clone, Default)]
+pub struct LogParser {
+    summary: LogSummary,
+    state: State,
+    /// The SCF cycle in progress
+    scf_cycle: Option<usize>,
+    /// The last link entered, such as "l502"
+    link: Option<String>,
+    /// True if the last line consumed is Gaussian termination
+    terminated: bool,
+    /// Incomplete line at the end of last chunk
+    pending: Vec<u8>,
+}
+
+impl LogParser {
+    /// Return the step for current geometry.
+    fn step(&mut self) -> &mut Step {
+        let steps = &mut self.summary.steps;
+        if steps.is_empty() {
+            steps.push(Step::default());
+        }
+        steps.last_mut().unwrap()
+    }
+
+    /// Consume a line of Gaussian log.
+    pub fn feed_line(&mut self, line: &str) {
+        let trimmed = line.trim();
+        // the next step in multi-step job could follow termination
+        if !trimmed.is_empty() {
+            // Gaussian writes job timing after error termination
+            let timing = ["Job cpu time:", "Elapsed time:", "File lengths"];
+            let terminated = trimmed.starts_with("Normal termination")
+                || trimmed.starts_with("Error termination")
+                || (self.terminated && timing.iter().any(|x| trimmed.starts_with(x)));
+            // the termination of previous job step
+            if self.terminated && !terminated {
+                self.summary.termination = None;
+            }
+            self.terminated = terminated;
+        }
+        match self.state {
+            State::Convergence => {
+                if let Some(item) = ConvergenceItem::parse(line) {
+                    self.step().convergence.push(item);
+                    return;
+                }
+            }
+            State::Eigenvalues => {
+                if line.contains("Eigenvalues ---") {
+                    return;
+                }
+            }
+            State::DipoleMoment => {
+                self.step().notes.push(trimmed.to_owned());
+                self.state = State::Normal;
+                return;
+            }
+            State::Normal => {}
+        }
+        self.state = State::Normal;
+
+        if let Some(link) = diagnosis::parse_link(trimmed) {
+            self.link = Some(link);
+        }
+        if let Some(failure) = Failure::detect(trimmed, self.link.as_deref()) {
+            self.summary.failure = Some(failure);
+        }
+
+        let summary = &mut self.summary;
+        if summary.revision.is_none() && trimmed.starts_with("Gaussian ") && trimmed.contains("Revision") {
+            summary.revision = Some(trimmed.trim_end_matches(',').to_owned());
+        } else if let Some(s) = trimmed.strip_prefix("Stoichiometry") {
+            summary.stoichiometry = Some(s.trim().to_owned());
+        } else if let Some(s) = trimmed.strip_prefix("Framework group") {
+            summary.framework_group = Some(s.trim().to_owned());
+        } else if let Some(s) = trimmed.strip_prefix("Deg. of freedom") {
+            summary.degrees_of_freedom = s.trim().parse().ok();
+        } else if let Some(s) = trimmed.strip_prefix("Standard basis:") {
+            summary.basis = Some(s.trim().to_owned());
+        } else if trimmed.starts_with("General basis") {
+            summary.basis = Some(trimmed.to_owned());
+        } else if trimmed.contains("basis functions,") {
+            summary.basis_functions = trimmed.split_whitespace().next().and_then(|x| x.parse().ok());
+        } else if trimmed.ends_with("orientation:") {
+            // a new geometry after energy calculation
+            if summary.steps.last().is_some_and(|x| x.scf_energy.is_some()) {
+                summary.steps.push(Step::default());
+            }
+        } else if let Some(n) = trimmed.strip_prefix("Cycle ") {
+            self.scf_cycle = n.split_whitespace().next().and_then(|x| x.parse().ok());
+        } else if let Some((method, energy, cycles)) = parse_scf_done(line) {
+            self.scf_cycle = None;
+            let step = self.step();
+            step.scf_method = Some(method);
+            step.scf_energy = Some(energy);
+            step.scf_cycles = cycles;
+        } else if let Some(s) = trimmed.strip_prefix("Step number") {
+            self.step().number = s.split_whitespace().next().and_then(|x| x.parse().ok());
+        } else if trimmed.ends_with("Converged?") {
+            self.step().convergence.clear();
+            self.state = State::Convergence;
+        } else if trimmed.starts_with("Number of steps in this run=")
+            || trimmed.contains("Convergence criterion not met")
+            || trimmed.contains("exceeded")
+            || trimmed.starts_with("Counterpoise:")
+            || trimmed.starts_with("Energy=")
+            || trimmed.starts_with("ONIOM: generating point")
+            || trimmed.starts_with("ONIOM: extrapolated energy")
+        {
+            self.step().notes.push(trimmed.to_owned());
+        } else if trimmed.starts_with("ONIOM: Dipole moment") {
+            self.step().notes.push(trimmed.to_owned());
+            self.state = State::DipoleMoment;
+        } else if line.contains("Eigenvalues ---") {
+            self.step().notes.push(trimmed.to_owned());
+            self.state = State::Eigenvalues;
+        } else if line.contains("WARNING") || line.contains("Warning") {
+            summary.warnings.push(trimmed.to_owned());
+        } else if trimmed.starts_with("Harmonic frequencies") {
+            summary.frequencies.clear();
+        } else if let Some(s) = trimmed.strip_prefix("Frequencies --") {
+            // skip high precision frequencies in "Frequencies ---" lines
+            if !s.starts_with('-') {
+                summary
+                    .frequencies
+                    .extend(s.split_whitespace().filter_map(|x| x.parse::<f64>().ok()));
+            }
+        } else if let Some((i, value)) = parse_thermochemistry(line) {
+            let thermo = summary.thermochemistry.get_or_insert_with(Default::default);
+            *thermo.values_mut()[i] = Some(value);
+        } else if trimmed.starts_with("Normal termination") || trimmed.starts_with("Error termination") {
+            let normal = trimmed.starts_with("Normal");
+            summary.termination = Some(Termination {
+                normal,
+                message: trimmed.to_owned(),
+            });
+            if normal {
+                summary.failure = None;
+            } else if let Some(failure) = summary.failure.as_mut() {
+                // the link terminated is more accurate
+                failure.link = self.link.clone();
+            } else {
+                summary.failure = Some(Failure::from_termination(trimmed, self.link.as_deref()));
+            }
+        } else if let Some(t) = parse_cpu_time(line) {
+            *summary.cpu_time.get_or_insert(0.0) += t;
+        }
+    }
+
+    /// Consume a chunk of Gaussian log, which may end with an incomplete
+    /// line to be completed by next chunk.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        while let Some(n) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<_> = self.pending.drain(..=n).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.feed_line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+
+    /// Return true if the log ends with Gaussian termination.
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Return a one-line status of the job being written in the log, such
+    /// as current step, SCF cycle and convergence.
+    pub fn status(&self) -> String {
+        let steps = &self.summary.steps;
+        let mut status = vec![];
+        match steps.iter().rev().find_map(|x| x.number) {
+            Some(n) => status.push(format!("step {n}")),
+            None => status.push(format!("geometry {}", steps.len())),
+        }
+        if let Some(n) = self.scf_cycle {
+            status.push(format!("SCF cycle {n}"));
+        } else if let Some(n) = steps.last().and_then(|x| x.scf_cycles) {
+            status.push(format!("SCF done after {n} cycles"));
+        }
+        if let Some(step) = steps.iter().rev().find(|x| x.scf_energy.is_some()) {
+            let method = step.scf_method.as_deref().unwrap_or_default();
+            status.push(format!("E({method}) = {:.10}", step.scf_energy.unwrap()));
+        }
+        if let Some(step) = steps.iter().rev().find(|x| !x.convergence.is_empty()) {
+            let converged = step
+                .convergence
+                .iter()
+                .map(|x| if x.converged { "YES" } else { "NO" })
+                .join(" ");
+            status.push(format!("converged: {converged}"));
+        }
+        if let Some(termination) = self.summary.termination.as_ref().filter(|_| self.terminated) {
+            status.push(termination.message.clone());
+        }
+        status.join(", ")
+    }
+
+    /// Return the summary of lines consumed so far.
+    pub fn summary(&self) -> &LogSummary {
+        &self.summary
+    }
+
+    /// Consume any incomplete line left and return the summary of all lines
+    /// consumed.
+    pub fn finish(mut self) -> LogSummary {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.feed_line(String::from_utf8_lossy(&line).trim_end_matches('\r'));
+        }
+        self.summary
+    }
+}
+
+impl LogSummary {
+    /// Parse all lines in Gaussian log from `r`.
+    pub fn from_reader(r: impl BufRead) -> Result<Self> {
+        let mut parser = LogParser::default();
+        for line in r.lines() {
+            parser.feed_line(&line?);
+        }
+        Ok(parser.finish())
+    }
+
+    /// Parse Gaussian log file `f`, which could be compressed.
+    pub fn from_file(f: &Path) -> Result<Self> {
+        let r = file_reader(f)?;
+        Self::from_reader(r).with_context(|| format!("Failed to parse Gaussian log {f:?}"))
+    }
+
+    /// Format the essential information in a human-readable text, in the
+    /// same lines as in Gaussian log.
+    pub fn to_text(&self) -> String {
+        let banner = format!(" {:-^72}\n", "");
+        let mut txt = String::new();
+        if let Some(s) = &self.revision {
+            txt.push_str(&format!(" {s}\n"));
+        }
+        if let Some(s) = &self.stoichiometry {
+            txt.push_str(&format!(" Stoichiometry    {s}\n"));
+        }
+        if let Some(s) = &self.framework_group {
+            txt.push_str(&format!(" Framework group  {s}\n"));
+        }
+        if let Some(s) = &self.basis {
+            txt.push_str(&format!(" Standard basis: {s}\n"));
+        }
+        if let Some(n) = self.basis_functions {
+            txt.push_str(&format!(" {n:>5} basis functions\n"));
+        }
+        if let Some(n) = self.degrees_of_freedom {
+            txt.push_str(&format!(" Deg. of freedom {n:>5}\n"));
+        }
+        txt.push_str(&banner);
+
+        for step in &self.steps {
+            for note in &step.notes {
+                txt.push_str(&format!(" {note}\n"));
+            }
+            if let (Some(method), Some(energy)) = (&step.scf_method, step.scf_energy) {
+                let cycles = step
+                    .scf_cycles
+                    .map(|n| format!(" after {n:>4} cycles"))
+                    .unwrap_or_default();
+                txt.push_str(&format!(" SCF Done:  E({method}) = {energy:16.10}     A.U.{cycles}\n"));
+            }
+            if let Some(n) = step.number {
+                txt.push_str(&format!(" Step number {n:>3}\n"));
+            }
+        }
+        txt.push_str(&banner);
+        // convergence of all steps in a compact table
+        if self.steps.iter().any(|x| !x.convergence.is_empty()) {
+            txt.push_str(&self.optimization_text());
+            txt.push_str(&banner);
+        }
+
+        if !self.frequencies.is_empty() {
+            let lowest = self.frequencies.iter().take(3).map(|x| format!("{x:>12.4}")).join(" ");
+            txt.push_str(&format!(" Frequencies -- {lowest}\n"));
+            let nimag = self.frequencies.iter().filter(|&&x| x < 0.0).count();
+            txt.push_str(&format!(" Number of imaginary frequencies: {nimag}\n"));
+        }
+        if let Some(thermo) = &self.thermochemistry {
+            for (label, value) in Thermochemistry::LABELS.into_iter().zip(thermo.values()) {
+                if let Some(value) = value {
+                    txt.push_str(&format!(" {:<44} {value:>14.6}\n", format!("{label}=")));
+                }
+            }
+            txt.push_str(&banner);
+        }
+        for warning in &self.warnings {
+            txt.push_str(&format!(" {warning}\n"));
+        }
+        if let Some(termination) = &self.termination {
+            txt.push_str(&format!(" {}\n", termination.message));
+        }
+        if let Some(failure) = &self.failure {
+            txt.push_str(&format!(" Failure: {failure}\n"));
+            if let Some(hint) = &failure.hint {
+                txt.push_str(&format!(" Hint: {hint}\n"));
+            }
+        }
+        if let Some(t) = self.cpu_time {
+            txt.push_str(&format!(" Job cpu time: {}\n", format_cpu_time(t)));
+        }
+        txt
+    }
+
+    /// Format as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Format SCF energies of all steps in CSV.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "step,method,energy,cycles\n".to_owned();
+        for (i, step) in self.steps.iter().enumerate() {
+            let method = step.scf_method.as_deref().unwrap_or_default();
+            let energy = step.scf_energy.map(|x| x.to_string()).unwrap_or_default();
+            let cycles = step.scf_cycles.map(|x| x.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{method},{energy},{cycles}\n", i + 1));
+        }
+        csv
+    }
+}
+// d1c47b95 ends here
+
+// [[file:../../xo-tools.note::2e95b7a1][2e95b7a1]]
+#[test]
+fn test_log_summary() -> Result<()> {
+    let line = " SCF Done:  E(UB3LYP) =  -150.367567881     A.U. after    9 cycles";
+    assert_eq!(parse_scf_done(line), Some(("UB3LYP".into(), -150.367567881, Some(9))));
+    let line = " Job cpu time:       0 days  1 hours  2 minutes  7.5 seconds.";
+    assert_eq!(parse_cpu_time(line), Some(3727.5));
+    assert_eq!(
+        format_cpu_time(3727.5).trim(),
+        "0 days  1 hours  2 minutes  7.5 seconds."
+    );
+    let line = " Maximum Displacement     0.022370     0.001800     NO ";
+    let item = ConvergenceItem::parse(line).unwrap();
+    assert_eq!(item.item, "Maximum Displacement");
+    assert_eq!((item.value, item.threshold, item.converged), (0.02237, 0.0018, false));
+    assert!(ConvergenceItem::parse(" Predicted change in Energy=-3.014512D-04").is_none());
+
+    let summary = LogSummary::from_file("tests/files/logs/h2o-opt-freq.log".as_ref())?;
+    assert_eq!(summary.revision.as_deref(), Some("Gaussian 16, Revision A.03"));
+    assert_eq!(summary.stoichiometry.as_deref(), Some("H2O"));
+    assert_eq!(summary.basis.as_deref(), Some("6-31G(d) (6D, 7F)"));
+    assert_eq!(summary.basis_functions, Some(19));
+    assert_eq!(summary.degrees_of_freedom, Some(2));
+    // three optimization steps and the frequency calculation
+    assert_eq!(summary.steps.len(), 4);
+    assert_eq!(summary.steps[0].scf_energy, Some(-76.4085432117));
+    assert_eq!(summary.steps[2].number, Some(3));
+    assert_eq!(summary.steps[0].convergence.len(), 4);
+    assert!(!summary.steps[0].convergence[0].converged);
+    assert!(summary.steps[2].convergence.iter().all(|x| x.converged));
+    assert!(summary.steps[3].convergence.is_empty());
+    assert_eq!(summary.steps[0].notes.len(), 1);
+    assert_eq!(summary.frequencies, [1713.0812, 3727.4137, 3849.1627]);
+    let thermo = summary.thermochemistry.as_ref().unwrap();
+    assert_eq!(thermo.zero_point_correction, Some(0.02118));
+    assert_eq!(thermo.free_energy, Some(-76.405373));
+    assert!(summary.termination.as_ref().unwrap().normal);
+    assert!((summary.cpu_time.unwrap() - 12.5).abs() < 1e-8);
+
+    let txt = summary.to_text();
+    assert!(txt.contains(" Sum of electronic and thermal Free Energies="));
+    assert!(!txt.contains("Converged?"));
+    assert!(txt.contains(&summary.optimization_text()));
+    let json = summary.to_json()?;
+    let summary_json: LogSummary = serde_json::from_str(&json)?;
+    assert_eq!(summary_json, summary);
+    let csv = summary.to_csv();
+    assert_eq!(csv.lines().count(), 1 + 4);
+    assert_eq!(csv.lines().nth(1), Some("1,RB3LYP,-76.4085432117,10"));
+
+    // single point calculation
+    let summary = LogSummary::from_file("tests/files/Job_o2.log".as_ref())?;
+    assert_eq!(summary.steps.len(), 1);
+    assert_eq!(summary.steps[0].scf_energy, Some(-150.367567881));
+    assert!(summary.frequencies.is_empty());
+    assert!(summary.thermochemistry.is_none());
+    assert_eq!(summary.cpu_time, Some(7.0));
+
+    Ok(())
+}
+// 2e95b7a1 ends here
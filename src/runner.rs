@@ -0,0 +1,142 @@
+// [[file:../xo-tools.note::f19c2b58][f19c2b58]]
+//! Run Gaussian program in different versions
+
+use super::*;
+use crate::error::GaussianError;
+// f19c2b58 ends here
+
+// [[file:../xo-tools.note::d3295ec9][d3295ec9]]
+// Setup gaussian runtime environment.
+//
+// create leading directories for Gaussian calculation per user.
+fn init_env() -> Result<PathBuf> {
+    let scrdir = std::env::var("GAUSS_SCRDIR").context("Get GAUSS_SCRDIR env var")?;
+    let user = std::env::var("USER").context("Get USER env var")?;
+    let scr_root_dir = Path::new(&scrdir).join(user);
+    info!("Scratching root dir: {:?}", scr_root_dir);
+
+    // create leading directories
+    // FIXME: potential permission issue for other users
+    std::fs::create_dir_all(&scr_root_dir).context("Create scratch directories")?;
+
+    Ok(scr_root_dir)
+}
+
+// Return exe name of Gaussian program providing path to a rc file
+//
+// remove version separator dot: g09.b02.rc ==> g09
+fn get_gaussian_exe_from_path(rcfile: &Path) -> Option<String> {
+    // make sure there is an entension in the path
+    let _ext = rcfile.extension()?;
+    let gxx = rcfile.file_name()?.to_str()?.split(".").next()?;
+    Some(gxx.into())
+}
+
+/// Return the last `n` lines in file `f`.
+fn read_last_lines(f: &Path, n: usize) -> Result<String> {
+    let s = gut::fs::read_file(f)?;
+    let lines: Vec<_> = s.lines().collect();
+    Ok(lines[lines.len().saturating_sub(n)..].join("\n"))
+}
+
+/// Run Gaussian program with `input`, using the runtime environment set up
+/// in `rcfile` like "g09.rc". The output is written into `output_file`, or
+/// into stdout if it is None.
+pub fn run_gaussian(input: &str, output_file: Option<PathBuf>, rcfile: &Path) -> Result<()> {
+    let scr_root_dir = init_env()?;
+    let tdir = tempfile::tempdir_in(scr_root_dir).context("Create scratching dir")?;
+    let scr_dir = tdir.path();
+    info!("Gaussian job scr dir: {:?}", scr_dir);
+
+    let gxx = get_gaussian_exe_from_path(rcfile).ok_or_else(|| GaussianError::InvalidFileName(rcfile.to_owned()))?;
+    let script = format!(
+        "#! /usr/bin/env bash
+
+source \"{rcfile}\"
+source \"${gxx}root/{gxx}/bsd/{gxx}.profile\"
+\"${gxx}root/{gxx}/{gxx}\"
+
+",
+        rcfile = rcfile.display(),
+        gxx = gxx,
+    );
+
+    info!("calling script: {:?}", script);
+    let runfile = scr_dir.join("run");
+    gut::fs::write_script_file(&runfile, &script)?;
+
+    if let Some(out) = output_file {
+        let status = duct::cmd!(runfile)
+            .env("GAUSS_SCRDIR", scr_dir)
+            .stdin_bytes(input)
+            .stdout_path(&out)
+            .run();
+        if let Err(e) = status {
+            let tail = read_last_lines(&out, 10).unwrap_or_default();
+            return Err(Error::from(e).context(GaussianError::GaussianTerminatedAbnormally { output: out, tail }));
+        }
+    } else {
+        duct::cmd!(runfile)
+            .env("GAUSS_SCRDIR", scr_dir)
+            .stdin_bytes(input)
+            .run()?;
+    }
+
+    info!("Gaussian job finished.");
+
+    Ok(())
+}
+
+/// Fix Windows line endings issue in Gaussian input `txt`.
+pub fn fix_line_endings_issue(txt: &str) -> String {
+    // convert to Unix line endings
+    let mut txt = txt.replace("\r", "");
+
+    // append a new line for avoiding a Gaussian bug
+    txt.push_str("\n");
+
+    txt
+}
+
+/// Return the path to rc file for Gaussian executable `gauss_exe`, which is
+/// in the same directory of the real executable binary.
+pub fn gaussian_rc_file(gauss_exe: &str) -> Result<PathBuf> {
+    // The path to real executable binary file
+    let real_path = std::env::current_exe().context("Failed to get exe path")?;
+    let rc_name = format!("{gauss_exe}.rc");
+    Ok(real_path.with_file_name(rc_name))
+}
+
+/// Guess an output file name from input file name
+pub fn guess_output_file(inp_file: &Path) -> Result<PathBuf> {
+    let out_file = inp_file.with_extension("log");
+    if out_file.extension() == inp_file.extension() {
+        return Err(GaussianError::InvalidFileName(inp_file.to_owned()).into());
+    }
+    Ok(out_file)
+}
+// d3295ec9 ends here
+
+// [[file:../xo-tools.note::*test][test:1]]
+#[test]
+fn test_xx() {
+    let p = Path::new("/share/apps/gaussian/bin/g03.rc");
+    assert_eq!(get_gaussian_exe_from_path(&p), Some("g03".into()));
+
+    let p = Path::new("/share/apps/gaussian/bin/g09.E01.rc");
+    assert_eq!(get_gaussian_exe_from_path(&p), Some("g09".into()));
+
+    let p = Path::new("/share/apps/gaussian/bin/g03");
+    assert_eq!(get_gaussian_exe_from_path(&p), None);
+
+    assert_eq!(
+        guess_output_file("a/Test001.gjf".as_ref()).unwrap(),
+        Path::new("a/Test001.log")
+    );
+    let e = guess_output_file("Test001.log".as_ref()).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::InvalidFileName(_))
+    ));
+}
+// test:1 ends here
@@ -0,0 +1,70 @@
+// [[file:../xo-tools.note::5d27c0e8][5d27c0e8]]
+//! Follow Gaussian output being written, like `tail -f`
+
+use super::*;
+
+use std::time::Duration;
+// 5d27c0e8 ends here
+
+// [[file:../xo-tools.note::a6f41b93][a6f41b93]]
+/// Incremental parser of Gaussian output which can be fed while Gaussian is
+/// still writing.
+pub(crate) trait Tail {
+    /// Consume a chunk of Gaussian output, which may end with an incomplete
+    /// line.
+    fn feed(&mut self, chunk: &[u8]) -> Result<()>;
+
+    /// Return true if the output ends with Gaussian termination. The next
+    /// job step may follow.
+    fn is_terminated(&self) -> bool;
+
+    /// Return true if nothing more is needed from the output.
+    fn is_complete(&self) -> bool {
+        false
+    }
+}
+
+/// Open file `f` for following.
+pub(crate) fn open(f: &Path) -> Result<std::fs::File> {
+    let file = std::fs::File::open(f).map_err(|source| error::GaussianError::IoError {
+        path: f.to_owned(),
+        source,
+    })?;
+    Ok(file)
+}
+
+/// Feed `parser` with data read from `r` while being written, checking for
+/// new data every `interval`. The `progress` callback is called with the
+/// parser after reading new data.
+///
+/// Reading stops when the parser is complete, or terminated without new
+/// job step in one more interval. It also stops if no new data is written
+/// for `timeout`, for Gaussian killed by scheduler or crashed node, which
+/// writes no termination. Return false in this case.
+pub(crate) fn follow<P: Tail>(
+    mut r: impl Read,
+    parser: &mut P,
+    interval: Duration,
+    timeout: Option<Duration>,
+    mut progress: impl FnMut(&P),
+) -> Result<bool> {
+    let mut buf = vec![0; 64 * 1024];
+    // the number of intervals without new data
+    let mut idle = 0;
+    loop {
+        let n = r.read(&mut buf)?;
+        if n > 0 {
+            parser.feed(&buf[..n])?;
+            progress(parser);
+            idle = 0;
+        } else if parser.is_complete() || (parser.is_terminated() && idle > 0) {
+            return Ok(true);
+        } else if timeout.is_some_and(|t| interval * idle >= t) {
+            return Ok(false);
+        } else {
+            std::thread::sleep(interval);
+            idle += 1;
+        }
+    }
+}
+// a6f41b93 ends here
@@ -3,40 +3,83 @@
 // 006191b3 ends here
 
 // [[file:../xo-tools.note::0c085add][0c085add]]
-#[allow(non_camel_case_types)]
+#![allow(non_camel_case_types)]
 
 use super::*;
+use serde::{Deserialize, Serialize};
 // 0c085add ends here
 
 // [[file:../xo-tools.note::101dbb9a][101dbb9a]]
 mod output;
+mod collector;
 mod input;
+mod report;
+mod custom;
+mod fit;
+mod geometry;
+mod job;
+mod reaction;
+mod spin;
+mod validate;
+
+pub use collector::Collector;
+pub use custom::{CustomFunctional, Presets};
+pub use fit::{Constraint, Dataset, Fitting};
+pub use geometry::Geometry;
+pub use input::{CoreOption, GaussianVersion, RewriteOptions};
+pub use output::{LineError, LineErrorKind};
+pub use reaction::{Reaction, Unit};
+pub use spin::SpinDiagnostics;
+pub use validate::JobType;
+pub use report::{NamedComponents, Report, ScanReport};
 // 101dbb9a ends here
 
 // [[file:../xo-tools.note::8e5ac845][8e5ac845]]
 const N: usize = 7;
 type Component = [f64; N];
 
+/// Names of energy components in the same order of functional parameters
+pub const COMPONENT_NAMES: [&str; N] = ["Ex(HF)", "Ex(S)", "Ex(B)", "Ec(VWN)", "Ec(LYP)", "E2(os)", "E2(ss)"];
+
 /// The xDH family of DH functionals
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Functional {
     XYG3,
     XYG5,
     XYG6,
     XYG7,
+    #[serde(rename = "XYGJ-OS")]
     XYGJ_OS,
     revXYG3,
 }
 
+#[derive(Debug, Clone)]
 pub struct xDH {
     energy_no_xc: f64,
     component: Component,
+    /// The SCF energy of B3LYP reference
+    energy_reference: f64,
+    /// The solvation energy term, which has been included in `energy_no_xc`
+    energy_solvation: Option<f64>,
+    /// The functional requested in Gaussian input, if recorded in output
+    functional: Option<Functional>,
+    /// The Gaussian output file parsed from, or None for stdin
+    source: Option<PathBuf>,
+    /// Spin diagnostics for open-shell reference
+    spin: Option<SpinDiagnostics>,
+    /// The geometry for which xDH results are calculated
+    geometry: Option<Geometry>,
 }
 // 8e5ac845 ends here
 
 // [[file:../xo-tools.note::75f764fb][75f764fb]]
 impl Functional {
-    fn parameters(&self) -> Component {
+    /// All functionals in the xDH family
+    pub fn all() -> [Self; 6] {
+        [Self::XYG3, Self::XYG5, Self::XYG6, Self::XYG7, Self::XYGJ_OS, Self::revXYG3]
+    }
+
+    pub fn parameters(&self) -> Component {
         match self {
             Self::XYG3 => [0.8033, -0.0140, 0.2107, 0.0000, 0.6789, 0.3211, 0.3211],
             Self::XYG5 => [0.9150, 0.0612, 0.0238, 0.0000, 0.4957, 0.4548, 0.2764],
@@ -49,17 +92,59 @@ impl Functional {
 }
 // 75f764fb ends here
 
+// [[file:../xo-tools.note::717a0b0f][717a0b0f]]
+/// The prefix of title line recording the requested functional, such as
+/// `xDH=XYG5`, which will be echoed in Gaussian output.
+const FUNCTIONAL_MARKER: &str = "xDH=";
+
+impl std::str::FromStr for Functional {
+    type Err = Error;
+
+    /// Parse functional name in route section, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        let functional = match s.to_uppercase().as_str() {
+            "XYG3" => Self::XYG3,
+            "XYG5" => Self::XYG5,
+            "XYG6" => Self::XYG6,
+            "XYG7" => Self::XYG7,
+            "XYGJ-OS" | "XYGJ_OS" | "XYGJOS" => Self::XYGJ_OS,
+            "REVXYG3" => Self::revXYG3,
+            _ => bail!("unknown xDH functional: {s:?}"),
+        };
+        Ok(functional)
+    }
+}
+
+impl std::fmt::Display for Functional {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::XYG3 => "XYG3",
+            Self::XYG5 => "XYG5",
+            Self::XYG6 => "XYG6",
+            Self::XYG7 => "XYG7",
+            Self::XYGJ_OS => "XYGJ-OS",
+            Self::revXYG3 => "revXYG3",
+        };
+        write!(f, "{name}")
+    }
+}
+// 717a0b0f ends here
+
 // [[file:../xo-tools.note::eb6caf8d][eb6caf8d]]
 impl xDH {
     /// Return final xDH functional energy from energy components
     pub fn energy(&self, functional: Functional) -> f64 {
-        let energy: f64 = self
-            .component
-            .into_iter()
-            .zip(functional.parameters())
-            .map(|(energy, param)| energy * param)
-            .sum();
-        energy + self.energy_no_xc
+        self.energy_with(&functional.parameters())
+    }
+
+    /// Return the functional requested in Gaussian input
+    pub fn requested_functional(&self) -> Option<Functional> {
+        self.functional
+    }
+
+    /// Return the SCF energy of B3LYP reference
+    pub fn reference_energy(&self) -> f64 {
+        self.energy_reference
     }
 }
 // eb6caf8d ends here
@@ -76,6 +161,21 @@ fn test_xdh_energy() -> Result<()> {
     assert_eq!(-149.5397701816522, xdh.energy(Functional::XYG7));
     assert_eq!(-149.96275308354984, xdh.energy(Functional::XYGJ_OS));
     assert_eq!(-150.25238782233433, xdh.energy(Functional::revXYG3));
+    assert_eq!(xdh.requested_functional(), None);
+
+    // compare with the report of xDH4Gau
+    let report = gut::fs::read_file("tests/files/o2.xDH")?;
+    assert!(report.contains(&format!("E(B3LYP)    = {:16.8} A.U.", xdh.reference_energy())));
+    for functional in Functional::all() {
+        let name = format!("E({functional})");
+        let line = format!("{name:<11} = {:16.8} A.U.", xdh.energy(functional));
+        assert!(report.contains(&line), "{line:?} not found in report");
+    }
+
+    assert_eq!("xygj-os".parse::<Functional>()?, Functional::XYGJ_OS);
+    assert_eq!("RevXYG3".parse::<Functional>()?, Functional::revXYG3);
+    assert_eq!(Functional::XYGJ_OS.to_string(), "XYGJ-OS");
+    assert!("B3LYP".parse::<Functional>().is_err());
 
     Ok(())
 }
@@ -0,0 +1,424 @@
+// [[file:../../xo-tools.note::4c8d2a71][4c8d2a71]]
+use super::*;
+
+use super::geometry::OrientationTable;
+use super::output::*;
+use super::spin::{parse_annihilation, parse_electrons, parse_multiplicity};
+use crate::error::GaussianError;
+use std::collections::VecDeque;
+// 4c8d2a71 ends here
+
+// [[file:../../xo-tools.note::b9e05f3a][b9e05f3a]]
+/// Energy terms collected for current geometry
+#[derive(Debug, Clone, Default)]
+struct Terms {
+    /// The last three ENTVJ lines for HF, B3LYP exchange and correlation parts
+    entvj: VecDeque<[f64; 4]>,
+    pt2_aa: Option<f64>,
+    pt2_ab: Option<f64>,
+    pt2_bb: Option<f64>,
+    solvation: Option<f64>,
+    reference: Option<f64>,
+    annihilation: Option<(f64, f64)>,
+}
+
+impl Terms {
+    fn is_empty(&self) -> bool {
+        self.reference.is_none() && self.entvj.is_empty() && self.pt2_ab.is_none()
+    }
+
+    fn missing(&self) -> Vec<&'static str> {
+        let mut missing = vec![];
+        if self.reference.is_none() {
+            missing.push("E(B3LYP)");
+        }
+        let n = self.entvj.len();
+        if n < 1 {
+            missing.extend(["E(noXC)", "Ex(HF)"]);
+        }
+        if n < 2 {
+            missing.extend(["Ex(S)", "Ec(VWN)"]);
+        }
+        if n < 3 {
+            missing.extend(["Ex(B)", "Ec(LYP)"]);
+        }
+        if self.pt2_ab.is_none() {
+            missing.push("E2(os)");
+        }
+        if self.pt2_aa.is_none() || self.pt2_bb.is_none() {
+            missing.push("E2(ss)");
+        }
+        missing
+    }
+}
+
+/// Incremental collector of xDH results from Gaussian output, which
+/// consumes each line only once. Chunks can be fed while Gaussian is still
+/// writing the output.
+///
+/// The output is segmented by geometry (the "orientation" table), so that
+/// xDH results of each point in scans, IRCs or multi-step jobs can be
+/// collected.
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    /// The number of lines consumed
+    line_number: usize,
+    /// True if the last line consumed is Gaussian termination
+    terminated: bool,
+    /// Incomplete line at the end of last chunk
+    pending: Vec<u8>,
+    terms: Terms,
+    functional: Option<Functional>,
+    multiplicity: Option<usize>,
+    electrons: Option<(usize, usize)>,
+    /// The coordinates table being parsed
+    orientation: Option<OrientationTable>,
+    geometry: Option<Geometry>,
+    /// xDH results of previous geometries
+    points: Vec<xDH>,
+}
+
+impl Collector {
+    /// Consume a complete line of Gaussian output.
+    pub fn feed_line(&mut self, line: &str) -> Result<()> {
+        self.line_number += 1;
+        let at = |e: LineErrorKind| e.at(self.line_number, line);
+        if let Some(table) = self.orientation.as_mut() {
+            if let Some(geometry) = table.feed_line(line).map_err(at)? {
+                self.geometry = Some(geometry);
+                self.orientation = None;
+            }
+            return Ok(());
+        }
+
+        let trimmed = line.trim_start();
+        let terms = &mut self.terms;
+        if line.contains("ENTVJ=") {
+            if terms.entvj.len() == 3 {
+                terms.entvj.pop_front();
+            }
+            terms.entvj.push_back(parse_entvj(line).map_err(at)?);
+        } else if trimmed.starts_with("alpha-alpha") {
+            terms.pt2_aa = Some(parse_os_ss(line).map_err(at)?);
+        } else if trimmed.starts_with("alpha-beta") {
+            terms.pt2_ab = Some(parse_os_ss(line).map_err(at)?);
+        } else if trimmed.starts_with("beta-beta") {
+            terms.pt2_bb = Some(parse_os_ss(line).map_err(at)?);
+        } else if trimmed.starts_with("Erf(P)=") {
+            terms.solvation = Some(parse_solvent(line).map_err(at)?);
+        } else if trimmed.starts_with("SCF Done:") {
+            terms.reference = Some(parse_scf_done(line).map_err(at)?);
+        } else if let Some(x) = parse_annihilation(line) {
+            terms.annihilation = Some(x);
+        } else if OrientationTable::is_header(line) {
+            // a new geometry after energy calculation
+            if !self.terms.is_empty() {
+                self.close_point();
+            }
+            self.orientation = Some(OrientationTable::default());
+        } else if trimmed.starts_with(FUNCTIONAL_MARKER) {
+            // the requested functional recorded in title
+            self.functional = self.functional.or(parse_requested_functional(line));
+        } else if let Some(x) = parse_multiplicity(line) {
+            self.multiplicity = Some(x);
+        } else if let Some(x) = parse_electrons(line) {
+            self.electrons = Some(x);
+        }
+        // the next step in multi-step job could follow termination
+        if !trimmed.is_empty() {
+            self.terminated = trimmed.starts_with("Normal termination") || trimmed.starts_with("Error termination");
+        }
+        Ok(())
+    }
+
+    /// Consume a chunk of Gaussian output, which may end with an incomplete
+    /// line to be completed by next chunk.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(chunk);
+        while let Some(n) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<_> = self.pending.drain(..=n).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.feed_line(line.trim_end_matches(['\n', '\r']))?;
+        }
+        Ok(())
+    }
+
+    /// Consume all lines from `r` until EOF.
+    pub fn feed_from(&mut self, mut r: impl BufRead) -> Result<()> {
+        loop {
+            let buf = r.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let n = buf.len();
+            self.feed(buf)?;
+            r.consume(n);
+        }
+        Ok(())
+    }
+
+    /// Return the names of energy terms not collected yet for current
+    /// geometry.
+    pub fn missing(&self) -> Vec<&'static str> {
+        self.terms.missing()
+    }
+
+    /// Return true if all energy terms have been collected for current
+    /// geometry.
+    pub fn is_complete(&self) -> bool {
+        self.missing().is_empty()
+    }
+
+    /// Return true if the output ends with Gaussian termination.
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Save xDH results of current geometry, and start collecting for a new
+    /// one. Incomplete results are ignored, such as the geometries in
+    /// optimization step before xDH calculation.
+    fn close_point(&mut self) {
+        let terms = std::mem::take(&mut self.terms);
+        let missing = terms.missing();
+        if !missing.is_empty() {
+            debug!("ignored geometry with missing energy terms: {}", missing.join(", "));
+            return;
+        }
+
+        let x = &terms.entvj;
+        let component = [
+            x[0][1],
+            x[1][1],
+            x[2][1],
+            x[1][2],
+            x[2][2],
+            terms.pt2_ab.unwrap(),
+            terms.pt2_aa.unwrap() + terms.pt2_bb.unwrap(),
+        ];
+        let spin = match (self.multiplicity, self.electrons, terms.annihilation) {
+            (Some(multiplicity), Some((alpha_electrons, beta_electrons)), Some((s2_before, s2_after))) => {
+                Some(SpinDiagnostics {
+                    multiplicity,
+                    alpha_electrons,
+                    beta_electrons,
+                    s2_before,
+                    s2_after,
+                })
+            }
+            _ => None,
+        };
+
+        self.points.push(xDH {
+            // the solvation energy term is included in the energy without XC
+            energy_no_xc: x[0][0] + terms.solvation.unwrap_or(0.0),
+            component,
+            energy_reference: terms.reference.unwrap(),
+            energy_solvation: terms.solvation,
+            functional: self.functional,
+            source: None,
+            spin,
+            geometry: self.geometry.clone(),
+        });
+    }
+
+    /// Consume any incomplete line left and save results of the last
+    /// geometry if complete. Return the names of energy terms missing for
+    /// the last geometry, as for a truncated output. Fail if no complete
+    /// results found.
+    fn close(&mut self) -> Result<Vec<&'static str>> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.feed_line(String::from_utf8_lossy(&line).trim_end_matches('\r'))?;
+        }
+        let missing = self.missing();
+        if missing.is_empty() {
+            self.close_point();
+        } else if self.points.is_empty() {
+            let message = format!("missing xDH energy terms in output stream: {}", missing.join(", "));
+            bail!(GaussianError::InvalidFormat(message));
+        } else if self.terms.is_empty() {
+            // no energy calculated for the last geometry
+            return Ok(vec![]);
+        }
+        Ok(missing)
+    }
+
+    /// Consume any incomplete line left and return the collected xDH results
+    /// for all geometries. The last geometry with missing energy terms is
+    /// ignored with a warning. Fail if no complete results found.
+    pub fn finish_all(mut self) -> Result<Vec<xDH>> {
+        let missing = self.close()?;
+        if !missing.is_empty() {
+            warn!(
+                "ignored last geometry with missing energy terms: {}",
+                missing.join(", ")
+            );
+        }
+        Ok(self.points)
+    }
+
+    /// Consume any incomplete line left and return the collected xDH
+    /// results for the last geometry. Fail if any energy term is missing
+    /// for the last geometry, even if results of previous geometries are
+    /// complete.
+    pub fn finish(mut self) -> Result<xDH> {
+        let missing = self.close()?;
+        if !missing.is_empty() {
+            let message = format!("missing xDH energy terms for the last geometry: {}", missing.join(", "));
+            bail!(GaussianError::InvalidFormat(message));
+        }
+        let xdh = self
+            .points
+            .pop()
+            .ok_or_else(|| GaussianError::InvalidFormat("no xDH results found in output stream".into()))?;
+        Ok(xdh)
+    }
+
+    /// Collect xDH results from all lines in `r`.
+    pub fn collect_from(r: impl BufRead) -> Result<xDH> {
+        let mut collector = Self::default();
+        collector.feed_from(r)?;
+        collector.finish()
+    }
+
+    /// Collect xDH results for all geometries from all lines in `r`.
+    pub fn collect_all_from(r: impl BufRead) -> Result<Vec<xDH>> {
+        let mut collector = Self::default();
+        collector.feed_from(r)?;
+        collector.finish_all()
+    }
+}
+// b9e05f3a ends here
+
+// [[file:../../xo-tools.note::e3f7a6d5][e3f7a6d5]]
+impl crate::tail::Tail for Collector {
+    fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        Collector::feed(self, chunk)
+    }
+
+    fn is_terminated(&self) -> bool {
+        Collector::is_terminated(self)
+    }
+
+    fn is_complete(&self) -> bool {
+        Collector::is_complete(self)
+    }
+}
+
+impl xDH {
+    /// Follow Gaussian output file `f` while being written, like `tail -f`,
+    /// until all energy terms are collected or Gaussian terminates without
+    /// further job step. The `progress` callback is called with the
+    /// collector after reading new data.
+    ///
+    /// Fail if the output is not written for `timeout` before all energy
+    /// terms are collected, as for a job killed without termination.
+    pub fn follow_gaussian(
+        f: &Path,
+        interval: std::time::Duration,
+        timeout: Option<std::time::Duration>,
+        progress: impl FnMut(&Collector),
+    ) -> Result<Self> {
+        let file = crate::tail::open(f)?;
+        let mut collector = Collector::default();
+        let finished =
+            crate::tail::follow(file, &mut collector, interval, timeout, progress).map_err(|e| with_file_name(e, f))?;
+        let mut xdh = collector.finish().map_err(|e| with_file_name(e, f));
+        if !finished {
+            xdh = xdh.with_context(|| format!("{f:?} not written in {timeout:?}, Gaussian may be killed"));
+        }
+        let mut xdh = xdh?;
+        xdh.source = Some(f.to_owned());
+        Ok(xdh)
+    }
+}
+// e3f7a6d5 ends here
+
+// [[file:../../xo-tools.note::6a0c95b8][6a0c95b8]]
+#[test]
+fn test_xdh_collector() -> Result<()> {
+    let s = gut::fs::read_file("tests/files/Job_o2.log")?;
+    let expected = Collector::collect_from(s.as_bytes())?;
+
+    // feed in small chunks splitting lines
+    let mut collector = Collector::default();
+    assert_eq!(collector.missing().len(), 9);
+    let mut seen_partial = false;
+    for chunk in s.as_bytes().chunks(97) {
+        collector.feed(chunk)?;
+        let missing = collector.missing();
+        if missing == ["Ex(S)", "Ec(VWN)", "Ex(B)", "Ec(LYP)"] {
+            seen_partial = true;
+        }
+    }
+    assert!(seen_partial);
+    assert!(collector.is_complete());
+    assert!(collector.is_terminated());
+    let xdh = collector.finish()?;
+    assert_eq!(xdh.energy(Functional::XYG3), expected.energy(Functional::XYG3));
+    assert_eq!(xdh.energy(Functional::XYG3), -150.25844295353738);
+    assert_eq!(xdh.spin, expected.spin);
+
+    // incomplete output
+    let n = s.find("ENTVJ=").unwrap();
+    let mut collector = Collector::default();
+    collector.feed(&s.as_bytes()[..n])?;
+    assert_eq!(
+        collector.missing(),
+        ["E(noXC)", "Ex(HF)", "Ex(S)", "Ec(VWN)", "Ex(B)", "Ec(LYP)"]
+    );
+    let e = collector.finish().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+
+    // the next job step truncated after complete results
+    let truncated = format!("{s}{}", &s[..n]);
+    let e = Collector::collect_from(truncated.as_bytes()).unwrap_err();
+    assert!(e.to_string().contains("for the last geometry: E(noXC)"));
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let points = Collector::collect_all_from(truncated.as_bytes())?;
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].energy(Functional::XYG3), expected.energy(Functional::XYG3));
+
+    // follow the output of a job killed before energy terms are written
+    let tdir = tempfile::tempdir()?;
+    let f = tdir.path().join("killed.log");
+    gut::fs::write_to_file(&f, &s[..n])?;
+    let interval = std::time::Duration::from_millis(1);
+    let timeout = Some(std::time::Duration::from_millis(5));
+    let mut updates = 0;
+    let e = xDH::follow_gaussian(&f, interval, timeout, |_| updates += 1).unwrap_err();
+    assert_eq!(updates, 1);
+    assert!(format!("{e:?}").contains("Gaussian may be killed"));
+    let f = Path::new("tests/files/Job_o2.log");
+    let xdh = xDH::follow_gaussian(f, interval, timeout, |_| {})?;
+    assert_eq!(xdh.energy(Functional::XYG3), expected.energy(Functional::XYG3));
+
+    Ok(())
+}
+
+#[test]
+fn test_xdh_collector_scan() -> Result<()> {
+    // the first geometry without xDH calculation is ignored
+    let f: &Path = "tests/files/xdh-scan.log".as_ref();
+    let points = xDH::collect_all_from_gaussian(f)?;
+    assert_eq!(points.len(), 2);
+    let o2 = xDH::collect_from_gaussian(Path::new("tests/files/Job_o2.log"))?;
+    assert_eq!(points[0].component, o2.component);
+    assert_eq!(points[0].energy_reference, o2.energy_reference);
+    assert_eq!(points[1].energy_reference, -150.367012345);
+    assert_eq!(points[1].component[0], -16.360758);
+    assert_eq!(points[1].component[5], -0.3652781731);
+    let g0 = points[0].geometry().unwrap();
+    let g1 = points[1].geometry().unwrap();
+    assert_eq!(g0.atomic_numbers, [8, 8]);
+    assert_eq!(g0.coordinates[1][0], -4.500626);
+    assert_eq!(g1.coordinates[1][0], -4.540626);
+    assert!(points.iter().all(|x| x.spin.is_some()));
+
+    // only the last geometry for single point
+    let xdh = xDH::collect_from_gaussian(f)?;
+    assert_eq!(xdh.energy_reference, -150.367012345);
+
+    Ok(())
+}
+// 6a0c95b8 ends here
@@ -0,0 +1,140 @@
+// [[file:../../xo-tools.note::944b55d4][944b55d4]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+// 944b55d4 ends here
+
+// [[file:../../xo-tools.note::0718542f][0718542f]]
+/// A xDH type functional defined by parameters for the seven energy
+/// components: Ex(HF), Ex(S), Ex(B), Ec(VWN), Ec(LYP), E2(os) and E2(ss).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomFunctional {
+    pub name: String,
+    pub parameters: Component,
+}
+
+impl From<Functional> for CustomFunctional {
+    fn from(functional: Functional) -> Self {
+        Self {
+            name: functional.to_string(),
+            parameters: functional.parameters(),
+        }
+    }
+}
+
+impl CustomFunctional {
+    /// Create a functional named as `name` from comma separated parameters,
+    /// such as "0.8033,-0.0140,0.2107,0.0,0.6789,0.3211,0.3211".
+    pub fn from_csv(name: &str, s: &str) -> Result<Self> {
+        let values: Vec<f64> = s
+            .split(',')
+            .map(|x| x.trim().parse())
+            .collect::<std::result::Result<_, _>>()
+            .with_context(|| format!("invalid xDH parameters: {s:?}"))?;
+        let parameters = values
+            .try_into()
+            .map_err(|x: Vec<f64>| format_err!("expect {N} xDH parameters, but found {}", x.len()))?;
+
+        Ok(Self {
+            name: name.into(),
+            parameters,
+        })
+    }
+}
+
+impl xDH {
+    /// Return the energy of a custom functional defined by `parameters`.
+    pub fn energy_with(&self, parameters: &Component) -> f64 {
+        let energy: f64 = self
+            .component
+            .into_iter()
+            .zip(parameters)
+            .map(|(energy, param)| energy * param)
+            .sum();
+        energy + self.energy_no_xc
+    }
+}
+// 0718542f ends here
+
+// [[file:../../xo-tools.note::a34edb94][a34edb94]]
+/// Named parameter sets for custom functionals, which can be shared in a
+/// TOML file like:
+///
+/// ```toml
+/// XYG3-mod = [0.8033, -0.0140, 0.2107, 0.0000, 0.6789, 0.3211, 0.3211]
+/// ```
+///
+/// or in an equivalent JSON file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Presets(BTreeMap<String, Component>);
+
+impl Presets {
+    /// Read presets from TOML or JSON file, determined by file extension.
+    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<Self> {
+        let f = f.as_ref();
+        let s = gut::fs::read_file(f)?;
+        let presets = if f.extension().is_some_and(|x| x == "json") {
+            serde_json::from_str(&s)?
+        } else {
+            toml::from_str(&s)?
+        };
+        Ok(presets)
+    }
+
+    /// Return all functionals defined in presets.
+    pub fn functionals(&self) -> Vec<CustomFunctional> {
+        self.0
+            .iter()
+            .map(|(name, &parameters)| CustomFunctional {
+                name: name.to_owned(),
+                parameters,
+            })
+            .collect()
+    }
+
+    /// Find functional by `name` in presets. The builtin functionals in xDH
+    /// family take precedence.
+    pub fn find(&self, name: &str) -> Result<CustomFunctional> {
+        if let Ok(functional) = name.parse::<Functional>() {
+            return Ok(functional.into());
+        }
+        let &parameters = self
+            .0
+            .get(name)
+            .ok_or(format_err!("no functional named {name:?} found"))?;
+        Ok(CustomFunctional {
+            name: name.into(),
+            parameters,
+        })
+    }
+}
+// a34edb94 ends here
+
+// [[file:../../xo-tools.note::250420cd][250420cd]]
+#[test]
+fn test_custom_functional() -> Result<()> {
+    let f: &Path = "tests/files/Job_o2.log".as_ref();
+    let xdh = xDH::collect_from_gaussian(f)?;
+
+    let custom = CustomFunctional::from_csv("custom", "0.8033, -0.0140, 0.2107, 0.0000, 0.6789, 0.3211, 0.3211")?;
+    assert_eq!(xdh.energy_with(&custom.parameters), xdh.energy(Functional::XYG3));
+    assert!(CustomFunctional::from_csv("custom", "0.8033, -0.0140").is_err());
+    assert!(CustomFunctional::from_csv("custom", "0.8033, x").is_err());
+
+    let presets = Presets::from_file("tests/files/xdh-presets.toml")?;
+    assert_eq!(presets.functionals().len(), 2);
+    let x = presets.find("XYG3-copy")?;
+    assert_eq!(xdh.energy_with(&x.parameters), xdh.energy(Functional::XYG3));
+    let x = presets.find("xyg5")?;
+    assert_eq!(x, Functional::XYG5.into());
+    assert!(presets.find("B2PLYP").is_err());
+
+    let presets: Presets =
+        serde_json::from_str(r#"{"XYG3-copy": [0.8033, -0.0140, 0.2107, 0.0, 0.6789, 0.3211, 0.3211]}"#)?;
+    assert_eq!(presets.find("XYG3-copy")?.parameters, Functional::XYG3.parameters());
+
+    Ok(())
+}
+// 250420cd ends here
@@ -0,0 +1,411 @@
+// [[file:../../xo-tools.note::5b0e7d21][5b0e7d21]]
+use super::*;
+
+use crate::error::GaussianError;
+use std::collections::HashMap;
+// 5b0e7d21 ends here
+
+// [[file:../../xo-tools.note::c2a6f0d9][c2a6f0d9]]
+/// Find the index of parameter by component name (ignoring case) or by
+/// 1-based position like `p3`.
+fn parameter_index(name: &str) -> Result<usize> {
+    if let Some(i) = COMPONENT_NAMES.iter().position(|x| x.eq_ignore_ascii_case(name)) {
+        return Ok(i);
+    }
+    let position = name.strip_prefix(['p', 'P']).and_then(|x| x.parse::<usize>().ok());
+    match position {
+        Some(i) if (1..=N).contains(&i) => Ok(i - 1),
+        _ => bail!(GaussianError::InvalidFormat(format!("unknown xDH parameter: {name:?}"))),
+    }
+}
+
+/// Split `s` into signed terms, such as "1e-3*E2(os)" and "-p3" in
+/// "1e-3*E2(os)-p3". The sign in exponent of number is kept in term.
+fn split_terms(s: &str) -> Vec<&str> {
+    let mut terms = vec![];
+    let mut start = 0;
+    let mut prev = [None, None];
+    for (i, c) in s.char_indices() {
+        let exponent = matches!(prev, [Some('0'..='9' | '.'), Some('e' | 'E')]);
+        if (c == '+' || c == '-') && !exponent {
+            terms.push(&s[start..i]);
+            start = i;
+        }
+        prev = [prev[1], Some(c)];
+    }
+    terms.push(&s[start..]);
+    terms
+        .into_iter()
+        .map(|x| x.strip_prefix('+').unwrap_or(x))
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// A linear constraint on xDH parameters, such as `Ex(HF)+Ex(S)+Ex(B)=1`,
+/// `E2(os)-E2(ss)=0` or `Ec(VWN)=0`. Parameters can be referred by
+/// component name or by 1-based position like `p3`, optionally with a
+/// coefficient like `2*E2(ss)` or `1e-3*p6`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    weights: Component,
+    value: f64,
+}
+
+impl std::str::FromStr for Constraint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |message: String| GaussianError::InvalidFormat(message);
+        let (lhs, rhs) = s
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("invalid constraint: {s:?}")))?;
+        let value = rhs
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("invalid constraint value: {rhs:?}")))?;
+        let mut weights = [0.0; N];
+        let lhs: String = lhs.split_whitespace().collect();
+        for term in split_terms(&lhs) {
+            let (coef, name) = match term.split_once('*') {
+                Some((coef, name)) => {
+                    let coef: f64 = coef
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid coefficient in {term:?}")))?;
+                    (coef, name)
+                }
+                None => match term.strip_prefix('-') {
+                    Some(name) => (-1.0, name),
+                    None => (1.0, term),
+                },
+            };
+            weights[parameter_index(name)?] += coef;
+        }
+        if weights.iter().all(|&x| x == 0.0) {
+            bail!(invalid(format!("no parameter in constraint: {s:?}")));
+        }
+
+        Ok(Self { weights, value })
+    }
+}
+// c2a6f0d9 ends here
+
+// [[file:../../xo-tools.note::8d41b6a3][8d41b6a3]]
+/// A reaction or a single system in dataset
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The reference energy in Hartree
+    reference: f64,
+    reaction: Reaction,
+}
+
+/// A set of reference data for fitting xDH parameters. Each line contains a
+/// reference energy in Hartree followed by a reaction or a single system
+/// (see `Reaction`):
+///
+/// ```text
+/// # reference  species
+/// -150.2584    o2.log
+/// -0.0412      -2*h2.log -1*o2.log 2*h2o.log
+/// -0.0412      2*h2.log + o2.log -> 2*h2o.log
+/// ```
+///
+/// Relative paths are resolved against the directory of dataset file.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub name: String,
+    entries: Vec<Entry>,
+}
+
+fn parse_dataset_entry(line: &str, dir: &Path) -> Result<Entry> {
+    let line = line.trim_start();
+    let (reference, reaction) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let reference = reference
+        .parse()
+        .map_err(|_| GaussianError::InvalidFormat(format!("invalid reference energy: {reference:?}")))?;
+    let reaction = reaction.parse::<Reaction>()?.with_base_dir(dir);
+
+    Ok(Entry { reference, reaction })
+}
+
+impl Dataset {
+    /// Read dataset from file `f`, named as the file stem.
+    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<Self> {
+        let f = f.as_ref();
+        let s = gut::fs::read_file(f)?;
+        let dir = f.parent().unwrap_or(Path::new(""));
+        let mut entries = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if !line.is_empty() {
+                let entry = parse_dataset_entry(line, dir).map_err(|e| {
+                    let context = GaussianError::ParseError {
+                        file: f.to_owned(),
+                        line: i + 1,
+                        message: e.to_string(),
+                    };
+                    e.context(context)
+                })?;
+                entries.push(entry);
+            }
+        }
+        if entries.is_empty() {
+            bail!(GaussianError::InvalidFormat(format!("no data found in {f:?}")));
+        }
+
+        let name = f
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Self { name, entries })
+    }
+}
+// 8d41b6a3 ends here
+
+// [[file:../../xo-tools.note::e47b9c10][e47b9c10]]
+/// The linear model of an entry in dataset: energy = base + components ·
+/// parameters
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    components: Component,
+    base: f64,
+    reference: f64,
+}
+
+impl Sample {
+    fn energy(&self, parameters: &Component) -> f64 {
+        self.base + self.components.iter().zip(parameters).map(|(x, p)| x * p).sum::<f64>()
+    }
+}
+
+/// Fitting errors of a dataset, in Hartree.
+#[derive(Debug, Clone)]
+pub struct Errors {
+    pub name: String,
+    pub count: usize,
+    pub mae: f64,
+    pub rmse: f64,
+}
+
+impl Errors {
+    fn new(name: &str, errors: &[f64]) -> Self {
+        let n = errors.len() as f64;
+        Self {
+            name: name.into(),
+            count: errors.len(),
+            mae: errors.iter().map(|x| x.abs()).sum::<f64>() / n,
+            rmse: (errors.iter().map(|x| x * x).sum::<f64>() / n).sqrt(),
+        }
+    }
+}
+
+/// Least-squares fitting of xDH parameters against reference data.
+/// Since xDH energy is linear in parameters, the fitting is analytic.
+#[derive(Debug, Clone)]
+pub struct Fitting {
+    datasets: Vec<(String, Vec<Sample>)>,
+}
+
+impl Fitting {
+    /// Collect energy components from Gaussian outputs in `datasets`. Each
+    /// output file is parsed only once.
+    pub fn new(datasets: &[Dataset]) -> Result<Self> {
+        let mut parsed: HashMap<&Path, xDH> = HashMap::new();
+        let mut fitting = Self { datasets: vec![] };
+        for dataset in datasets {
+            let mut samples = vec![];
+            for entry in &dataset.entries {
+                let mut sample = Sample {
+                    components: [0.0; N],
+                    base: 0.0,
+                    reference: entry.reference,
+                };
+                for (coef, path) in &entry.reaction.species {
+                    if !parsed.contains_key(path.as_path()) {
+                        let xdh = xDH::collect_from_gaussian(path.as_path())
+                            .with_context(|| format!("Failed to collect xDH components from {path:?}"))?;
+                        parsed.insert(path, xdh);
+                    }
+                    let xdh = &parsed[path.as_path()];
+                    sample.base += coef * xdh.energy_no_xc;
+                    for (x, c) in sample.components.iter_mut().zip(xdh.component) {
+                        *x += coef * c;
+                    }
+                }
+                samples.push(sample);
+            }
+            fitting.datasets.push((dataset.name.clone(), samples));
+        }
+
+        Ok(fitting)
+    }
+
+    /// Fit parameters minimizing the sum of squared errors over all
+    /// datasets, subject to linear `constraints`.
+    #[allow(clippy::needless_range_loop)]
+    pub fn fit(&self, constraints: &[Constraint]) -> Result<Component> {
+        // the KKT equations of equality constrained least squares
+        let n = N + constraints.len();
+        let mut a = vec![vec![0.0; n]; n];
+        let mut b = vec![0.0; n];
+        for s in self.datasets.iter().flat_map(|(_, samples)| samples) {
+            let y = s.reference - s.base;
+            for i in 0..N {
+                b[i] += s.components[i] * y;
+                for j in 0..N {
+                    a[i][j] += s.components[i] * s.components[j];
+                }
+            }
+        }
+        for (k, c) in constraints.iter().enumerate() {
+            for i in 0..N {
+                a[N + k][i] = c.weights[i];
+                a[i][N + k] = c.weights[i];
+            }
+            b[N + k] = c.value;
+        }
+
+        let x = solve_linear_equations(a, b)
+            .context("Failed to fit xDH parameters: insufficient data or conflicting constraints")?;
+        Ok(x[..N].try_into().unwrap())
+    }
+
+    /// Return fitting errors with `parameters` for each dataset, followed by
+    /// the errors over all datasets.
+    pub fn errors(&self, parameters: &Component) -> Vec<Errors> {
+        let mut all = vec![];
+        let mut errors = vec![];
+        for (name, samples) in &self.datasets {
+            let e: Vec<_> = samples.iter().map(|s| s.energy(parameters) - s.reference).collect();
+            errors.push(Errors::new(name, &e));
+            all.extend(e);
+        }
+        errors.push(Errors::new("all", &all));
+        errors
+    }
+}
+
+/// Solve linear equations `a x = b` using Gaussian elimination with partial
+/// pivoting.
+#[allow(clippy::needless_range_loop)]
+fn solve_linear_equations(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Result<Vec<f64>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0_f64, |m, x| m.max(x.abs()));
+    for k in 0..n {
+        let p = (k..n).max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs())).unwrap();
+        if a[p][k].abs() <= scale * 1e-14 {
+            bail!("singular linear equations");
+        }
+        a.swap(k, p);
+        b.swap(k, p);
+        for i in k + 1..n {
+            let f = a[i][k] / a[k][k];
+            for j in k..n {
+                a[i][j] -= f * a[k][j];
+            }
+            b[i] -= f * b[k];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for k in (0..n).rev() {
+        let s: f64 = (k + 1..n).map(|j| a[k][j] * x[j]).sum();
+        x[k] = (b[k] - s) / a[k][k];
+    }
+    Ok(x)
+}
+// e47b9c10 ends here
+
+// [[file:../../xo-tools.note::0f3d8e56][0f3d8e56]]
+#[test]
+fn test_xdh_fit_constraint() -> Result<()> {
+    let c: Constraint = "Ex(HF) + ex(s)+p3 = 1".parse()?;
+    assert_eq!(c.weights, [1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(c.value, 1.0);
+    let c: Constraint = "E2(os) - 2*E2(ss) = -0.5".parse()?;
+    assert_eq!(c.weights, [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, -2.0]);
+    assert_eq!(c.value, -0.5);
+    // coefficients in scientific notation
+    let c: Constraint = "1e-3*E2(os) - 2.5E+1*P7 + -1E2*Ec(VWN) = 0".parse()?;
+    assert_eq!(c.weights, [0.0, 0.0, 0.0, -100.0, 0.0, 0.001, -25.0]);
+    let e = "Ec(PBE) = 0".parse::<Constraint>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let e = "Ec(VWN) = x".parse::<Constraint>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let e = "x*Ec(VWN) = 0".parse::<Constraint>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    assert!("8 = 0".parse::<Constraint>().is_err());
+    assert!("p8 = 0".parse::<Constraint>().is_err());
+    // bare numbers are not parameter positions
+    assert!("Ex(HF)+Ex(S)+3 = 1".parse::<Constraint>().is_err());
+    assert!("Ec(VWN)".parse::<Constraint>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_xdh_fit() -> Result<()> {
+    // synthetic data generated with XYG3 parameters
+    let xyg3 = Functional::XYG3.parameters();
+    let samples: Vec<_> = (0..20)
+        .map(|i| {
+            let components = std::array::from_fn(|j| -(((i * 7 + j * 13) % 17) as f64) - (i * j) as f64 * 0.1 - 1.0);
+            let mut s = Sample {
+                components,
+                base: -(i as f64),
+                reference: 0.0,
+            };
+            s.reference = s.energy(&xyg3);
+            s
+        })
+        .collect();
+    let fitting = Fitting {
+        datasets: vec![("test".into(), samples)],
+    };
+    let params = fitting.fit(&[])?;
+    assert!(params.iter().zip(xyg3).all(|(x, y)| (x - y).abs() < 1e-8));
+    let errors = fitting.errors(&params);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].name, "all");
+    assert!(errors[1].rmse < 1e-8);
+
+    let constraints = ["Ec(VWN) = 0.1".parse()?, "Ex(HF)+Ex(S)+Ex(B) = 1".parse()?];
+    let params = fitting.fit(&constraints)?;
+    assert!((params[3] - 0.1).abs() < 1e-10);
+    assert!((params[0] + params[1] + params[2] - 1.0).abs() < 1e-10);
+    assert!(fitting.errors(&params)[0].mae > 1e-4);
+
+    // conflicting constraints
+    let constraints = ["Ec(VWN) = 0.1".parse()?, "Ec(VWN) = 0.2".parse()?];
+    assert!(fitting.fit(&constraints).is_err());
+
+    // real data but insufficient for fitting
+    let dataset = Dataset::from_file("tests/files/xdh-fit.dat")?;
+    assert_eq!(dataset.name, "xdh-fit");
+    let fitting = Fitting::new(&[dataset])?;
+    let errors = fitting.errors(&xyg3);
+    assert_eq!(errors[0].count, 3);
+    assert!(errors[0].mae < 1e-8);
+    assert!(fitting.fit(&[]).is_err());
+    // E2(ss) parameter is recovered from the real output with the others
+    // fixed to XYG3
+    let constraints: Vec<Constraint> = COMPONENT_NAMES[..N - 1]
+        .iter()
+        .zip(xyg3)
+        .map(|(name, p)| format!("{name} = {p}").parse())
+        .try_collect()?;
+    let params = fitting.fit(&constraints)?;
+    assert!((params[N - 1] - xyg3[N - 1]).abs() < 1e-6);
+
+    // invalid line in dataset file
+    let tdir = tempfile::tempdir()?;
+    let f = tdir.path().join("bad.dat");
+    gut::fs::write_to_file(&f, "# reference  species\n-150.2584 o2.log\nx h2o.log\n")?;
+    let e = Dataset::from_file(&f).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::ParseError { line: 3, .. })
+    ));
+    assert_eq!(crate::error::exit_code(&e), 4);
+
+    Ok(())
+}
+// 0f3d8e56 ends here
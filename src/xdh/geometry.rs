@@ -0,0 +1,91 @@
+// [[file:../../xo-tools.note::2d7f9c46][2d7f9c46]]
+use super::*;
+
+use super::output::{parse_fortran_number, LineErrorKind, ParseResult};
+use serde::{Deserialize, Serialize};
+// 2d7f9c46 ends here
+
+// [[file:../../xo-tools.note::a85e3b17][a85e3b17]]
+/// Molecular geometry in Gaussian output, with coordinates in Angstrom
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Geometry {
+    pub atomic_numbers: Vec<usize>,
+    pub coordinates: Vec<[f64; 3]>,
+}
+
+/// Incremental parser of coordinates table following "Input orientation:"
+/// or "Standard orientation:" line:
+///
+/// ```text
+///  ---------------------------------------------------------------------
+///  Center     Atomic      Atomic             Coordinates (Angstroms)
+///  Number     Number       Type             X           Y           Z
+///  ---------------------------------------------------------------------
+///       1          8           0       -3.339026   -1.011956    0.000000
+///       2          8           0       -4.500626   -1.011956    0.000000
+///  ---------------------------------------------------------------------
+/// ```
+#[derive(Debug, Clone, Default)]
+pub(super) struct OrientationTable {
+    separators: usize,
+    geometry: Geometry,
+}
+
+impl OrientationTable {
+    /// Check if `line` starts a coordinates table.
+    pub fn is_header(line: &str) -> bool {
+        let line = line.trim();
+        line == "Input orientation:" || line == "Standard orientation:"
+    }
+
+    /// Consume a line in the table. Return the parsed geometry at the end
+    /// of the table.
+    pub fn feed_line(&mut self, line: &str) -> ParseResult<Option<Geometry>> {
+        if line.trim_start().starts_with("----") {
+            self.separators += 1;
+            if self.separators == 3 {
+                return Ok(Some(std::mem::take(&mut self.geometry)));
+            }
+        } else if self.separators == 2 {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            let [_, z, _, x, y, zz] = parts[..] else {
+                return Err(LineErrorKind::MissingField("Coordinates".into()));
+            };
+            let z = z.parse().map_err(|_| LineErrorKind::InvalidNumber {
+                field: "Atomic Number".into(),
+                value: z.into(),
+            })?;
+            let coords = [("X", x), ("Y", y), ("Z", zz)].map(|(field, value)| parse_fortran_number(field, value));
+            let [x, y, zz] = coords;
+            self.geometry.atomic_numbers.push(z);
+            self.geometry.coordinates.push([x?, y?, zz?]);
+        }
+        Ok(None)
+    }
+}
+
+impl xDH {
+    /// Return the geometry associated with xDH results, if available.
+    pub fn geometry(&self) -> Option<&Geometry> {
+        self.geometry.as_ref()
+    }
+}
+// a85e3b17 ends here
+
+// [[file:../../xo-tools.note::5e1b0c8a][5e1b0c8a]]
+#[test]
+fn test_orientation_table() -> Result<()> {
+    let s = gut::fs::read_file("tests/files/Job_o2.log")?;
+    let mut lines = s.lines().skip_while(|line| !OrientationTable::is_header(line)).skip(1);
+    let mut table = OrientationTable::default();
+    let geometry = loop {
+        if let Some(g) = table.feed_line(lines.next().unwrap()).unwrap() {
+            break g;
+        }
+    };
+    assert_eq!(geometry.atomic_numbers, [8, 8]);
+    assert_eq!(geometry.coordinates[1], [-4.500626, -1.011956, 0.0]);
+
+    Ok(())
+}
+// 5e1b0c8a ends here
@@ -3,75 +3,242 @@ use super::*;
 // imports:1 ends here
 
 // [[file:../../xo-tools.note::f45e0853][f45e0853]]
-use regex::Regex;
+use super::validate::{requests_xdh_in_oniom, validate_job_step};
+use crate::input::{resolve_includes, GaussianInput, GaussianJobs, IOp, Keyword, Route, Section};
 
-/// Reference: https://gaussian.com/route/
-fn rewrite_route_section(s: &str) -> Result<String> {
-    // a pound sign (#) as the first non-blank character of a line
-    if !s.trim_start().starts_with("#") {
-        bail!("invalid route section: {s:?}");
-    }
+/// Return the xDH functional requested in route section `route`
+fn requests_xdh(route: &Route) -> Option<Functional> {
+    route.keywords().find_map(|kw| kw.name.parse().ok())
+}
 
-    // turn on additional output: "#P"
-    let re = Regex::new(r"^(?i)\s*#[NPT]{0,1}").unwrap();
-    let reformed = re.replace(s, "#p");
-
-    #[rustfmt::skip]
-    let re = Regex::new(r"(?x)
-(?i)XYG3               # XYG3, case insensitive
-(?P<core>\(\w+\)){0,1} # XYG3(FULL) or XYG3(FC)
-").unwrap();
-    let caps = re.captures(&reformed).ok_or(format_err!("no XYG3 route line found"))?;
-    let mut reformed: String = re.replace(reformed.trim_end(), "B3LYP").to_lowercase();
-    // iop(5/33=1) nosymm extraoverlay
-    if !reformed.contains("iop(5/33=1)") {
-        reformed.push_str(" iop(5/33=1)");
-    }
-    if !reformed.contains("nosymm") {
-        reformed.push_str(" nosymm");
+/// Frozen core options for the MP2 step in xDH calculation, specified as
+/// functional options like XYG3(FULL), XYG3(FC), XYG3(FC1) or
+/// XYG3(Window=(m,n)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoreOption {
+    /// All electrons are correlated.
+    #[default]
+    Full,
+    /// Inner shells are frozen.
+    FrozenCore,
+    /// Inner shells are frozen, except the outer core of the third row and
+    /// heavier elements.
+    FC1,
+    /// Only orbitals in the window (m, n) are correlated, as in Gaussian's
+    /// `Window=(m,n)` option.
+    Window(isize, isize),
+}
+
+impl CoreOption {
+    /// Parse a frozen core option. Return None if `s` is not a frozen core
+    /// option.
+    fn parse(s: &str) -> Option<Result<Self>> {
+        let x = s.to_uppercase();
+        let core = match x.as_str() {
+            "FULL" => Self::Full,
+            "FC" => Self::FrozenCore,
+            "FC1" => Self::FC1,
+            _ => {
+                let window = x.strip_prefix("WINDOW=")?;
+                let window = window
+                    .strip_prefix('(')
+                    .and_then(|w| w.strip_suffix(')'))
+                    .unwrap_or(window);
+                let parse = |w: &str| w.trim().parse().with_context(|| format!("invalid window: {s:?}"));
+                let core = match window.split_once(',') {
+                    Some((m, n)) => parse(m).and_then(|m| Ok(Self::Window(m, parse(n)?))),
+                    None => parse(window).map(|m| Self::Window(m, 0)),
+                };
+                return Some(core);
+            }
+        };
+        Some(Ok(core))
     }
-    if !reformed.contains("extraoverlay") {
-        reformed.push_str(" extraoverlay");
+
+    /// IOps in overlay 8 for the frozen core option
+    fn iops(&self) -> String {
+        match self {
+            Self::Full => "10=90".into(),
+            Self::FrozenCore => "10=4".into(),
+            Self::FC1 => "10=5".into(),
+            Self::Window(m, n) => format!("6={m},9={n},10=2"),
+        }
     }
+}
+
+/// Options for xDH functional keyword, such as XYG3(FC1,RO) or
+/// XYG3(Window=(5,0),Ref=B3LYP).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FunctionalOptions {
+    core: CoreOption,
+    /// Restricted open-shell reference calculation
+    restricted_open_shell: bool,
+}
 
-    // turn on frozen core algorithm or not
-    let frozen_core = if let Some(core_type) = caps.name("core") {
-        match core_type.as_str().to_uppercase().as_str() {
-            "(FULL)" => false,
-            "(FC)" => true,
-            x @ _ => {
-                bail!("invalid syntax: {s:?}");
+impl FunctionalOptions {
+    fn parse(options: &[String]) -> Result<Self> {
+        let mut core = None;
+        let mut restricted_open_shell = false;
+        for option in options {
+            if let Some(x) = CoreOption::parse(option) {
+                if core.replace(x?).is_some() {
+                    bail!("conflicting frozen core options: {options:?}");
+                }
+            } else if option.eq_ignore_ascii_case("RO") {
+                restricted_open_shell = true;
+            } else if let Some((key, value)) = option.split_once('=') {
+                if !(key.eq_ignore_ascii_case("Ref") || key.eq_ignore_ascii_case("Reference")) || value.is_empty() {
+                    bail!("invalid functional option: {option:?}");
+                }
+                // the energy terms are collected and combined with
+                // parameters fitted against B3LYP reference
+                if !value.eq_ignore_ascii_case("B3LYP") {
+                    bail!("unsupported reference {value:?}: xDH functionals are defined with B3LYP reference");
+                }
+            } else {
+                bail!("invalid functional option: {option:?}");
             }
         }
-    } else {
-        false
-    };
 
-    // write extra overlays
-    writeln!(&mut reformed, "\n")?;
-    // 'AE':['8/7=1,10=90/1;','9/16=-3/6;','6//8;'],
-    // 'FC':['8/7=1,10=4/1;','9/16=-3/6;','6//8;']
-    if frozen_core {
-        writeln!(&mut reformed, "8/7=1,10=4/1;")?;
-    } else {
-        writeln!(&mut reformed, "8/7=1,10=90/1;")?;
+        Ok(Self {
+            core: core.unwrap_or_default(),
+            restricted_open_shell,
+        })
+    }
+}
+
+/// Reference: https://gaussian.com/route/
+///
+/// Return the reformed route line, the requested xDH functional and the
+/// frozen core option.
+fn rewrite_route_section(s: &str) -> Result<(String, Functional, CoreOption)> {
+    let mut route: Route = s.parse()?;
+
+    // turn on additional output: "#P"
+    route.print_level = Some('P');
+
+    let (method, functional) = route
+        .keywords_mut()
+        .find_map(|kw| {
+            let functional: Functional = kw.name.parse().ok()?;
+            Some((kw, functional))
+        })
+        .ok_or(format_err!("no xDH functional found in route: {s:?}"))?;
+
+    // frozen core, open-shell and reference options: XYG3(FC,RO,Ref=B3LYP)
+    let options = FunctionalOptions::parse(&method.options).with_context(|| format!("invalid syntax: {s:?}"))?;
+    let prefix = if options.restricted_open_shell { "RO" } else { "" };
+    *method = Keyword::new(&format!("{prefix}B3LYP"));
+
+    // iop(5/33=1) nosymm extraoverlay
+    route.merge_iops(&[IOp::new(5, 33, 1)])?;
+    route.require_no_symmetry()?;
+    route.require_keyword("extraoverlay");
+
+    Ok((route.to_string().to_lowercase(), functional, options.core))
+}
+
+/// Gaussian versions with known extra overlays for the MP2 step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GaussianVersion {
+    G03,
+    #[default]
+    G09,
+    G16,
+}
+
+impl std::str::FromStr for GaussianVersion {
+    type Err = Error;
+
+    /// Parse version from Gaussian executable name like "g16", or rc file
+    /// name like "g09.b02"
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.split('.').next().unwrap_or(s);
+        match name.to_lowercase().as_str() {
+            "g03" => Ok(Self::G03),
+            "g09" => Ok(Self::G09),
+            "g16" => Ok(Self::G16),
+            _ => bail!("unsupported Gaussian version: {s:?}"),
+        }
     }
-    writeln!(&mut reformed, "9/16=-3/6;")?;
-    writeln!(&mut reformed, "6//8;")?;
+}
 
-    Ok(reformed)
+/// Extra overlays for the MP2 step in each Gaussian version, in which
+/// "{core}" will be replaced with IOps for frozen core option. The overlays
+/// are the same for the versions supported so far.
+const EXTRA_OVERLAYS: [(GaussianVersion, [&str; 3]); 3] = [
+    (GaussianVersion::G03, ["8/7=1,{core}/1;", "9/16=-3/6;", "6//8;"]),
+    (GaussianVersion::G09, ["8/7=1,{core}/1;", "9/16=-3/6;", "6//8;"]),
+    (GaussianVersion::G16, ["8/7=1,{core}/1;", "9/16=-3/6;", "6//8;"]),
+];
+
+/// Extra overlays for the MP2 step with frozen core option `core` in
+/// Gaussian `version`
+fn extra_overlays(core: CoreOption, version: GaussianVersion) -> Section {
+    let (_, lines) = EXTRA_OVERLAYS
+        .iter()
+        .find(|(v, _)| *v == version)
+        .expect("extra overlays for all Gaussian versions");
+    let iops = core.iops();
+    Section::new(lines.map(|x| x.replace("{core}", &iops)))
 }
 
 #[test]
 #[rustfmt::skip]
 fn test_xdh_reform_route() -> Result<()> {
+    use GaussianVersion::*;
+
     let x = rewrite_route_section("# XYG3/6-31g")?;
-    assert_eq!(x, "#p b3lyp/6-31g iop(5/33=1) nosymm extraoverlay\n\n8/7=1,10=90/1;\n9/16=-3/6;\n6//8;\n");
+    assert_eq!(x, ("#p b3lyp/6-31g iop(5/33=1) nosymm extraoverlay".into(), Functional::XYG3, CoreOption::Full));
+    assert_eq!(extra_overlays(x.2, G09).lines, ["8/7=1,10=90/1;", "9/16=-3/6;", "6//8;"]);
 
     let x = rewrite_route_section("# xyg3(full)/6-31g** nosymm test")?;
-    assert_eq!(x, "#p b3lyp/6-31g** nosymm test iop(5/33=1) extraoverlay\n\n8/7=1,10=90/1;\n9/16=-3/6;\n6//8;\n");
+    assert_eq!(x, ("#p b3lyp/6-31g** nosymm test iop(5/33=1) extraoverlay".into(), Functional::XYG3, CoreOption::Full));
     let x = rewrite_route_section("# xyg3(fc)/6-31g** nosymm test")?;
-    assert_eq!(x, "#p b3lyp/6-31g** nosymm test iop(5/33=1) extraoverlay\n\n8/7=1,10=4/1;\n9/16=-3/6;\n6//8;\n");
+    assert_eq!(x, ("#p b3lyp/6-31g** nosymm test iop(5/33=1) extraoverlay".into(), Functional::XYG3, CoreOption::FrozenCore));
+    assert_eq!(extra_overlays(x.2, G09).lines, ["8/7=1,10=4/1;", "9/16=-3/6;", "6//8;"]);
+
+    // other functionals in xDH family
+    let x = rewrite_route_section("# XYGJ-OS(FC)/6-31g")?;
+    assert_eq!(x, ("#p b3lyp/6-31g iop(5/33=1) nosymm extraoverlay".into(), Functional::XYGJ_OS, CoreOption::FrozenCore));
+    let x = rewrite_route_section("# revxyg3 6-31g")?;
+    assert_eq!(x, ("#p b3lyp 6-31g iop(5/33=1) nosymm extraoverlay".into(), Functional::revXYG3, CoreOption::Full));
+    for functional in ["XYG5", "XYG6", "XYG7"] {
+        let x = rewrite_route_section(&format!("# {functional}(full)/6-31g"))?;
+        assert_eq!(x.1, functional.parse()?);
+    }
+
+    // required options in different forms should not be duplicated
+    let x = rewrite_route_section("#P XYG3/6-31g IOp(5/33=1) NoSymmetry ExtraOverlay")?;
+    assert_eq!(x.0, "#p b3lyp/6-31g iop(5/33=1) nosymmetry extraoverlay");
+    let x = rewrite_route_section("#n XYG3/6-31g Symmetry=None iop(3/76=1000007400,\n5/33=1)")?;
+    assert_eq!(x.0, "#p b3lyp/6-31g symmetry=none iop(3/76=1000007400,5/33=1) extraoverlay");
+
+    // other frozen core options, open-shell and reference options
+    let x = rewrite_route_section("# XYG3(FC1)/6-31g")?;
+    assert_eq!(x.2, CoreOption::FC1);
+    assert_eq!(extra_overlays(x.2, G16).lines[0], "8/7=1,10=5/1;");
+    let x = rewrite_route_section("# XYG3(Window=(5,-2))/6-31g")?;
+    assert_eq!(x.2, CoreOption::Window(5, -2));
+    assert_eq!(extra_overlays(x.2, G03).lines[0], "8/7=1,6=5,9=-2,10=2/1;");
+    assert_eq!(rewrite_route_section("# XYG3(window=3)/6-31g")?.2, CoreOption::Window(3, 0));
+    let x = rewrite_route_section("# XYG3(FC,RO)/6-31g")?;
+    assert_eq!(x, ("#p rob3lyp/6-31g iop(5/33=1) nosymm extraoverlay".into(), Functional::XYG3, CoreOption::FrozenCore));
+    let x = rewrite_route_section("# XYG3(FC,Ref=B3LYP)/6-31g")?;
+    assert_eq!(x, ("#p b3lyp/6-31g iop(5/33=1) nosymm extraoverlay".into(), Functional::XYG3, CoreOption::FrozenCore));
+
+    // conflicting options
+    assert!(rewrite_route_section("# XYG3/6-31g IOp(5/33=2)").is_err());
+    assert!(rewrite_route_section("# XYG3(FC2)/6-31g").is_err());
+    assert!(rewrite_route_section("# XYG3(FC,Full)/6-31g").is_err());
+    assert!(rewrite_route_section("# XYG3(Window=(a,0))/6-31g").is_err());
+    assert!(rewrite_route_section("# XYG3(Ref=)/6-31g").is_err());
+    // only B3LYP reference is supported for the fitted parameters
+    assert!(rewrite_route_section("# XYG3(Ref=PBE1PBE)/6-31g").is_err());
+
+    assert_eq!("g16".parse::<GaussianVersion>()?, G16);
+    assert_eq!("g09.b02".parse::<GaussianVersion>()?, G09);
+    assert!("g98".parse::<GaussianVersion>().is_err());
 
     Ok(())
 }
@@ -79,38 +246,50 @@ fn test_xdh_reform_route() -> Result<()> {
 
 // [[file:../../xo-tools.note::98726040][98726040]]
 // gen basis set
-fn rewrite_final_section(s: &str) -> Result<String> {
+fn rewrite_final_section() -> Section {
     // write extra data in the end
-    let reformed = format!("{}\n\n100\n205\n402\n", s.trim_end());
-
-    Ok(reformed)
+    Section::new(["100", "205", "402"])
 }
 // 98726040 ends here
 
 // [[file:../../xo-tools.note::8b2a8f8c][8b2a8f8c]]
+/// Options for rewriting Gaussian input for xDH calculation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RewriteOptions {
+    /// Keep include lines untouched instead of inlining the included files.
+    pub keep_includes: bool,
+    /// The Gaussian version for selecting extra overlays
+    pub version: GaussianVersion,
+}
+
 impl xDH {
     /// Rewrite Gaussian input stream `f` to make it suitable for XYG3 type
     /// calculation. If f is None, it will read from stdin. The reformed stream
     /// will be printed in stdout.
-    pub fn rewrite_gaussian_input<'a>(f: impl Into<Option<&'a Path>>) -> Result<String> {
-        if let Some(f) = f.into() {
+    ///
+    /// Files in include lines like "@basis.gbs /N" are inlined, which are
+    /// resolved relative to the directory of input file, or to current
+    /// directory for stdin. See also `RewriteOptions`.
+    pub fn rewrite_gaussian_input<'a>(f: impl Into<Option<&'a Path>>, options: RewriteOptions) -> Result<String> {
+        let (s, base_dir) = if let Some(f) = f.into() {
             info!("Reading Gaussian input from {f:?} ...");
-            let s = file_reader(f)?;
-            Self::rewrite_gaussian_input_from(s)
+            (Self::read_gaussian_input(f)?, f.parent().unwrap_or(Path::new("")))
         } else {
             info!("Reading Gaussian input from stdin ...");
-            let s = std::io::stdin().lock();
-            Self::rewrite_gaussian_input_from(s)
-        }
+            (Self::read_gaussian_input_from(stdin_reader()?)?, Path::new(""))
+        };
+        let s = if options.keep_includes {
+            s
+        } else {
+            resolve_includes(&s, base_dir)?
+        };
+        Self::rewrite_gaussian_jobs(&s, options.version)
     }
 
     fn read_gaussian_input_from(s: impl BufRead) -> Result<String> {
         let mut reformed = String::new();
         for line in s.lines() {
             let line = line?;
-            if line.trim_start().starts_with("@") {
-                debug!("found at file: {line}");
-            }
             writeln!(&mut reformed, "{line}")?;
         }
 
@@ -123,39 +302,53 @@ impl xDH {
         Self::read_gaussian_input_from(f)
     }
 
-    /// Rewrite Gaussian input file `s` to make it suitable for XYG3 type
-    /// calculation
-    fn rewrite_gaussian_input_from(f: impl BufRead) -> Result<String> {
-        let s = Self::read_gaussian_input_from(f)?;
-        let re = Regex::new(r"\n\s*\n").unwrap();
-        let sections = re.split(s.trim()).collect_vec();
-        let n = sections.len();
-        if n < 3 {
-            bail!("invalid Gaussian input: {s:?}");
-        }
+    /// Rewrite Gaussian input `s` to make it suitable for XYG3 type
+    /// calculation. For multi-step input, only the job steps requesting xDH
+    /// functional will be rewritten, and others are kept untouched.
+    fn rewrite_gaussian_jobs(s: &str, version: GaussianVersion) -> Result<String> {
+        let mut jobs: GaussianJobs = s.parse()?;
 
-        // link0 lines are the lines starting with "%" char
-        // for route lines, the first line must be starting with "#"
-        let mut lines = sections[0].lines();
-        let mut link0 = String::new();
-        let mut route = String::new();
-        while let Some(line) = lines.next() {
-            if line.starts_with("%") {
-                writeln!(&mut link0, "{line}")?;
+        let mut nrewritten = 0;
+        for (i, step) in jobs.steps.iter_mut().enumerate() {
+            let route = step
+                .parse_route()
+                .with_context(|| format!("Invalid route in job step {}", i + 1))?;
+            if requests_xdh(&route).is_some() || requests_xdh_in_oniom(&route) {
+                validate_job_step(step).with_context(|| format!("Incompatible job type in job step {}", i + 1))?;
+                rewrite_job_step(step, version).with_context(|| format!("Failed to rewrite job step {}", i + 1))?;
+                nrewritten += 1;
             } else {
-                writeln!(&mut route, "{line}")?;
+                info!("job step {} does not request xDH functional, kept untouched.", i + 1);
             }
         }
+        if nrewritten == 0 {
+            bail!("no xDH functional found in route section");
+        }
 
-        let route = rewrite_route_section(&route)?;
-        let other = sections[1..n - 1].join("\n\n");
-
-        let gen = rewrite_final_section(&sections[n - 1])?;
+        Ok(jobs.to_string())
+    }
+}
 
-        let s = format!("{link0}{route}\n{other}\n\n{gen}");
+/// Rewrite a single job step for XYG3 type calculation
+fn rewrite_job_step(input: &mut GaussianInput, version: GaussianVersion) -> Result<()> {
+    let route = input.route.joined();
+    if input.molecule.is_none() && !input.parse_route()?.geom_allcheck() {
+        bail!("invalid Gaussian input: {input}");
+    }
 
-        Ok(s)
+    let (route, functional, core) = rewrite_route_section(&route)?;
+    input.route.lines = vec![route];
+    // record the requested functional in title for reporting energy later.
+    // No line is added, as the title section is limited to 5 lines.
+    if let Some(line) = input.title.as_mut().and_then(|x| x.lines.first_mut()) {
+        *line = format!("{FUNCTIONAL_MARKER}{functional} {line}");
+    } else {
+        warn!("no title section to record the requested {functional}, which must be given with -f to obtain results.");
     }
+    input.overlay = Some(extra_overlays(core, version));
+    input.push_section(rewrite_final_section());
+
+    Ok(())
 }
 // 8b2a8f8c ends here
 
@@ -163,15 +356,85 @@ impl xDH {
 #[test]
 fn test_rewrite_input() -> Result<()> {
     let f: &Path = "tests/files/Test009.gjf".as_ref();
-    let x = xDH::rewrite_gaussian_input(f)?;
-    println!("reformed input\n{x}");
+    let x = xDH::rewrite_gaussian_input(f, RewriteOptions::default())?;
+    let input: GaussianInput = x.parse()?;
+    let original = GaussianInput::from_file(f)?;
+    assert_eq!(input.link0, original.link0);
+    assert_eq!(
+        input.route.lines,
+        ["#p b3lyp/6-311+g(3df,2p) sp scf=tight iop(5/33=1) nosymm extraoverlay"]
+    );
+    assert_eq!(
+        input.overlay.as_ref().unwrap().lines,
+        ["8/7=1,10=90/1;", "9/16=-3/6;", "6//8;"]
+    );
+    assert_eq!(
+        input.title.as_ref().unwrap().lines[0],
+        "xDH=XYG3 Test for Oniom-Type input ::"
+    );
+    assert_eq!(input.molecule_spec(), original.molecule_spec());
+    assert_eq!(input.trailing.last().unwrap().lines, ["100", "205", "402"]);
+
+    // optimization is turned into single point, and frequency is refused
+    let s = "# XYG3/6-31g opt\n\ntitle\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n";
+    let x = xDH::rewrite_gaussian_jobs(s, GaussianVersion::G09)?;
+    assert!(x.starts_with("#p b3lyp/6-31g iop(5/33=1) nosymm extraoverlay\n"));
+    let s = "# XYG3/6-31g freq\n\ntitle\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n";
+    assert!(xDH::rewrite_gaussian_jobs(s, GaussianVersion::G09).is_err());
 
     let f: &Path = "tests/files/Test008.gjf".as_ref();
-    let x = xDH::rewrite_gaussian_input(f)?;
+    let keep_includes = RewriteOptions {
+        keep_includes: true,
+        ..Default::default()
+    };
+    let x = xDH::rewrite_gaussian_input(f, keep_includes)?;
 
     let x_expected = gut::fs::read_file("tests/files/Job_Test008.com")?;
     assert_eq!(x, x_expected);
 
+    // the include file of gen basis is inlined
+    let x = xDH::rewrite_gaussian_input(f, RewriteOptions::default())?;
+    let basis = gut::fs::read_file("tests/files/Test008.H")?;
+    assert_eq!(x, x_expected.replace("@Test008.H  /N\n", &basis));
+
+    // the requested functional is recorded in title
+    let s = "# XYG5(FC)/6-31g\n\ntitle\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n";
+    let x = xDH::rewrite_gaussian_jobs(s, GaussianVersion::G09)?;
+    assert!(x.contains("\n\nxDH=XYG5 title\n\n"));
+    // without exceeding 5 title lines
+    let s = "# XYG3/6-31g\n\nline 1\nline 2\nline 3\nline 4\nline 5\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n";
+    let x = xDH::rewrite_gaussian_jobs(s, GaussianVersion::G09)?;
+    let input: GaussianInput = x.parse()?;
+    let title = input.title.unwrap();
+    assert_eq!(title.lines.len(), 5);
+    assert_eq!(title.lines[0], "xDH=XYG3 line 1");
+    assert_eq!(
+        super::output::parse_requested_functional(&title.lines[0]),
+        Some(Functional::XYG3)
+    );
+    // no title to record for geom=allcheck
+    let s = "%chk=x.chk\n# XYG7/6-31g geom=allcheck guess=read\n\n";
+    let x = xDH::rewrite_gaussian_jobs(s, GaussianVersion::G09)?;
+    assert!(!x.contains(FUNCTIONAL_MARKER));
+    assert!(x.contains("\n6//8;\n\n100\n205\n402\n"));
+
+    // only the second step requests XYG3
+    let f: &Path = "tests/files/Test011.gjf".as_ref();
+    let x = xDH::rewrite_gaussian_input(f, RewriteOptions::default())?;
+    let x_expected = gut::fs::read_file("tests/files/Job_Test011.com")?;
+    assert_eq!(x, x_expected);
+
+    // XYG3 single point followed by another job step
+    let s = gut::fs::read_file(f)?;
+    let (opt, sp) = s.split_once("--Link1--\n").unwrap();
+    let s = format!("{sp}--Link1--\n{opt}");
+    let x = xDH::rewrite_gaussian_jobs(&s, GaussianVersion::G09)?;
+    assert!(x.contains("\n100\n205\n402\n\n--Link1--\n%chk=Test011.chk\n# b3lyp/6-31g(d) opt\n"));
+    let jobs: GaussianJobs = x.parse()?;
+    assert_eq!(jobs.steps.len(), 2);
+    assert_eq!(jobs.steps[0].trailing.last().unwrap().lines, ["100", "205", "402"]);
+    assert_eq!(jobs.steps[1].to_string(), opt);
+
     Ok(())
 }
 // 5a888014 ends here
@@ -0,0 +1,20 @@
+// [[file:../../xo-tools.note::c7a95e21][c7a95e21]]
+use super::*;
+
+use crate::runner::{fix_line_endings_issue, run_gaussian};
+// c7a95e21 ends here
+
+// [[file:../../xo-tools.note::4b0e6f3d][4b0e6f3d]]
+impl xDH {
+    /// Rewrite Gaussian input `inpfile` for xDH calculation, run Gaussian
+    /// with the runtime environment in `rcfile`, and collect xDH results
+    /// from the output written into `outfile`.
+    pub fn run_gaussian(inpfile: &Path, outfile: &Path, rcfile: &Path, options: RewriteOptions) -> Result<Self> {
+        let input = Self::rewrite_gaussian_input(inpfile, options)?;
+        let input = fix_line_endings_issue(&input);
+        info!("Running Gaussian job with output written to {outfile:?} ...");
+        run_gaussian(&input, Some(outfile.to_owned()), rcfile)?;
+        Self::collect_from_gaussian(outfile).with_context(|| format!("Failed to collect xDH results from {outfile:?}"))
+    }
+}
+// 4b0e6f3d ends here
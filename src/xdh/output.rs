@@ -1,229 +1,256 @@
 // [[file:../../xo-tools.note::146c5546][146c5546]]
 use super::*;
+
+use crate::error::GaussianError;
 // 146c5546 ends here
 
-// [[file:../../xo-tools.note::c11365a3][c11365a3]]
-/// Read all relevant lines for XYG3 from Gaussian generated log file
-fn extract_relevant_lines(f: &Path) -> Result<Vec<String>> {
-    let f = file_reader(f)?;
-    extract_relevant_lines_from(f)
+// [[file:../../xo-tools.note::86b74a1f][86b74a1f]]
+/// The reason of failure in parsing a line of Gaussian output
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineErrorKind {
+    /// A required field is missing
+    MissingField(String),
+    /// The field is filled with asterisks due to overflow in Fortran format
+    Overflow(String),
+    /// The field value is not a valid number
+    InvalidNumber { field: String, value: String },
 }
 
-/// Read all relevant lines for XYG3 from Gaussian generated log file
-fn extract_relevant_lines_from(s: impl BufRead) -> Result<Vec<String>> {
-    #[rustfmt::skip]
-    let keywords = ["ENTVJ=", "SCF Done:", "alpha-beta", "alpha-alpha", "beta-beta", "Erf(P)="];
-
-    let lines = s
-        .lines()
-        .filter_map(move |line| {
-            // NOTE: line is wrapped in a Result type for UTF-8 issue
-            let line = line.ok()?;
-            if keywords.iter().any(|key| line.contains(key)) {
-                Some(line)
-            } else {
-                None
-            }
+/// Error in parsing a line of Gaussian output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineError {
+    /// 1-based line number in Gaussian output
+    pub line_number: usize,
+    pub line: String,
+    pub kind: LineErrorKind,
+}
+
+impl std::fmt::Display for LineErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "missing field {field:?}"),
+            Self::Overflow(field) => write!(f, "overflow in field {field:?}"),
+            Self::InvalidNumber { field, value } => write!(f, "invalid number {value:?} in field {field:?}"),
+        }
+    }
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}: {:?}", self.line_number, self.kind, self.line)
+    }
+}
+
+impl std::error::Error for LineError {}
+
+impl LineErrorKind {
+    /// Attach the 1-based `line_number` and the `line` to this error.
+    pub(super) fn at(self, line_number: usize, line: &str) -> LineError {
+        LineError {
+            line_number,
+            line: line.to_owned(),
+            kind: self,
+        }
+    }
+}
+
+/// Attach Gaussian output file name `f` to line error in `err` if any, as
+/// `GaussianError::ParseError`.
+pub(super) fn with_file_name(err: Error, f: &Path) -> Error {
+    match err.downcast_ref::<LineError>() {
+        Some(e) => {
+            let context = GaussianError::ParseError {
+                file: f.to_owned(),
+                line: e.line_number,
+                message: e.kind.to_string(),
+            };
+            err.context(context)
+        }
+        None => err,
+    }
+}
+
+pub(super) type ParseResult<T> = std::result::Result<T, LineErrorKind>;
+
+/// Return labelled values in `line` like "ENTVJ=-1261.054618 Ex= -116.450185",
+/// without relying on column positions.
+fn labelled_values(line: &str) -> Vec<(&str, &str)> {
+    let mut pairs = vec![];
+    let mut rest = line;
+    while let Some((left, right)) = rest.split_once('=') {
+        let label = left.split_whitespace().last().unwrap_or_default();
+        let right = right.trim_start();
+        let n = right.find(char::is_whitespace).unwrap_or(right.len());
+        pairs.push((label, &right[..n]));
+        rest = &right[n..];
+    }
+    pairs
+}
+
+/// Parse a number in Fortran format, which could use `D` exponent or be
+/// filled with asterisks on overflow.
+pub(super) fn parse_fortran_number(field: &str, value: &str) -> ParseResult<f64> {
+    if value.starts_with('*') {
+        return Err(LineErrorKind::Overflow(field.into()));
+    }
+    value
+        .replace(['D', 'd'], "E")
+        .parse()
+        .map_err(|_| LineErrorKind::InvalidNumber {
+            field: field.into(),
+            value: value.into(),
         })
-        .collect();
+}
 
-    Ok(lines)
+/// Find and parse the value of `field` in labelled `values`.
+fn field_value(values: &[(&str, &str)], field: &str) -> ParseResult<f64> {
+    let (_, value) = values
+        .iter()
+        .find(|(label, _)| *label == field)
+        .ok_or(LineErrorKind::MissingField(field.into()))?;
+    parse_fortran_number(field, value)
 }
-// c11365a3 ends here
 
-// [[file:../../xo-tools.note::86b74a1f][86b74a1f]]
 //  SCF Done:  E(UB3LYP) =  -150.367567881     A.U. after    9 cycles
-fn parse_scf_done(line: &str) -> Option<f64> {
-    if line.starts_with(" SCF Done:") {
-        line[24..44].trim().parse().ok()
-    } else {
-        None
-    }
+pub(super) fn parse_scf_done(line: &str) -> ParseResult<f64> {
+    let values = labelled_values(line);
+    let (label, value) = values.first().ok_or(LineErrorKind::MissingField("E(...)".into()))?;
+    parse_fortran_number(label, value)
 }
 // 86b74a1f ends here
 
 // [[file:../../xo-tools.note::e68b3776][e68b3776]]
 // ENTVJ= -133.281125 Ex=  -16.365355 Ec=    0.000000 ETotM2e= -234.7283473371  ETot= -149.6464806455
 // ENTVJ= -363.840442 Ex=  -50.358635 Ec=   -5.600997 ETotM2e=-1072.3996282781  ETot= -419.8000737661
-fn parse_entvj(line: &str) -> Option<[f64; 4]> {
-    // NOTE: could be invalid when the number is large
-    // let parts = line.split_whitespace().collect_vec();
-    let parts: Vec<_> = line
-        .split('=')
-        .skip(1)
-        .filter_map(|s| {
-            let x = s.split_whitespace().next()?;
-            x.parse::<f64>().ok()
-        })
-        .collect();
-    assert_eq!(parts.len(), 5, "invalid line: {line:?}");
-
-    Some([parts[0], parts[1], parts[2], parts[3]])
+pub(super) fn parse_entvj(line: &str) -> ParseResult<[f64; 4]> {
+    let values = labelled_values(line);
+    Ok([
+        field_value(&values, "ENTVJ")?,
+        field_value(&values, "Ex")?,
+        field_value(&values, "Ec")?,
+        field_value(&values, "ETotM2e")?,
+    ])
 }
 // e68b3776 ends here
 
 // [[file:../../xo-tools.note::0491bdad][0491bdad]]
 // alpha-beta  T2 =       0.1397256845D+00 E2=     -0.3642781731D+00
-fn parse_os_ss(line: &str) -> Option<f64> {
-    if line.starts_with("     alpha-") || line.starts_with("     beta-") {
-        let value = line[49..].replace("D", "E").trim().parse().ok()?;
-        Some(value)
-    } else {
-        None
-    }
+pub(super) fn parse_os_ss(line: &str) -> ParseResult<f64> {
+    field_value(&labelled_values(line), "E2")
 }
 
 #[test]
 fn test_xdh_os_ss() {
     let line = "     alpha-beta  T2 =       0.1397256845D+00 E2=     -0.3642781731D+00";
-    let x = parse_os_ss(line);
+    let x = parse_os_ss(line).ok();
     assert_eq!(x, Some(-0.3642781731));
 
     let line = "     alpha-alpha T2 =       0.1912645165D-01 E2=     -0.6748185095D-01";
-    let x = parse_os_ss(line);
+    let x = parse_os_ss(line).ok();
     assert_eq!(x, Some(-0.06748185095));
 
     let line = "     beta-beta   T2 =       0.7629260704D-01 E2=     -0.1043875581D+00";
-    let x = parse_os_ss(line);
+    let x = parse_os_ss(line).ok();
     assert_eq!(x, Some(-0.1043875581));
 
     // ENTVJ= -363.840442 Ex=  -50.358635 Ec=   -5.600997 ETotM2e=-1072.3996282781  ETot= -419.8000737661
     let line = "            ENTVJ= -363.840442 Ex=  -50.358635 Ec=   -5.600997 ETotM2e=-1072.3996282781  ETot= -419.8000737661";
-    // let line = "            ENTVJ=-1261.054618 Ex= -116.450185 Ec=    0.000000 ETotM2e=-2749.2509298729  ETot=-1377.5048029040";
-    let parts: Vec<_> = line
-        .split('=')
-        .skip(1)
-        .filter_map(|s| {
-            let x = s.split_whitespace().next()?;
-            x.parse::<f64>().ok()
-        })
-        .collect();
-    dbg!(parts);
     let parts = parse_entvj(&line);
-    assert!(parts.is_some());
+    assert!(parts.is_ok());
     assert_eq!(parts.unwrap()[3], -1072.3996282781);
+
+    // wide numbers without separating spaces
+    let line = "            ENTVJ=-1261.054618 Ex= -116.450185 Ec=    0.000000 ETotM2e=-2749.2509298729  ETot=-1377.5048029040";
+    assert_eq!(
+        parse_entvj(line),
+        Ok([-1261.054618, -116.450185, 0.0, -2749.2509298729])
+    );
+
+    // overflow in Fortran format
+    let line =
+        "            ENTVJ=*********** Ex= -116.450185 Ec=    0.000000 ETotM2e=-2749.2509298729  ETot=-1377.5048029040";
+    assert_eq!(parse_entvj(line), Err(LineErrorKind::Overflow("ENTVJ".into())));
+    let line = "            ENTVJ=-1261.054618 Ex= -116.450185";
+    assert_eq!(parse_entvj(line), Err(LineErrorKind::MissingField("Ec".into())));
 }
 // 0491bdad ends here
 
 // [[file:../../xo-tools.note::029f58f8][029f58f8]]
 //  Erf(P)=          -0.000578845929
-fn parse_solvent(line: &str) -> Option<f64> {
-    if line.starts_with(" Erf(P)= ") {
-        line[9..].trim().parse().ok()
-    } else {
-        None
-    }
+pub(super) fn parse_solvent(line: &str) -> ParseResult<f64> {
+    field_value(&labelled_values(line), "Erf(P)")
 }
 
 #[test]
 fn test_parse_solvent() {
     let line = " Erf(P)=          -0.000578845929";
-    assert_eq!(parse_solvent(line), Some(-0.000578845929));
+    assert_eq!(parse_solvent(line).ok(), Some(-0.000578845929));
 }
-// 029f58f8 ends here
 
-// [[file:../../xo-tools.note::f1ce30a8][f1ce30a8]]
-fn collect_energy_components_from_file(f: &Path) -> Result<(f64, Component)> {
-    let r = file_reader(f)?;
-    collect_energy_components_from(r)
+#[test]
+fn test_parse_scf_done() {
+    let line = " SCF Done:  E(UB3LYP) =  -150.367567881     A.U. after    9 cycles";
+    assert_eq!(parse_scf_done(line), Ok(-150.367567881));
+    let line = " SCF Done:  E(RB3LYP) =  -2749.25092987291     A.U. after   17 cycles";
+    assert_eq!(parse_scf_done(line), Ok(-2749.25092987291));
+    let line = " SCF Done:  E(RB3LYP) =  ****************     A.U. after   17 cycles";
+    assert!(matches!(parse_scf_done(line), Err(LineErrorKind::Overflow(_))));
 }
+// 029f58f8 ends here
 
-fn collect_energy_components_from(s: impl BufRead) -> Result<(f64, Component)> {
-    let lines = extract_relevant_lines_from(s)?;
-
-    let mut comp = Component::default();
-    let mut energy_no_xc = f64::NAN;
-    let mut energy_pt2 = [f64::NAN; 2];
-
-    let p: Option<Vec<_>> = lines
-        .iter()
-        .filter(|line| line.contains("ENTVJ="))
-        .map(|line| parse_entvj(line))
-        .collect();
-    if let Some(x) = p {
-        if x.len() != 3 {
-            bail!("no ENTVJ line found in output stream");
-        }
-        energy_no_xc = x[0][0];
-        comp[0] = x[0][1];
-        comp[1] = x[1][1];
-        comp[2] = x[2][1];
-        comp[3] = x[1][2];
-        comp[4] = x[2][2];
-    } else {
-        bail!("Error happens in collecting the DFT components in output stream");
-    }
-
-    // collect PT2 energy terms
-    let p: Option<_> = lines
-        .iter()
-        .filter(|line| line.contains("alpha-alpha"))
-        .last()
-        .map(|line| parse_os_ss(line));
-    if let Some(Some(x)) = p {
-        energy_pt2[1] = x;
-    } else {
-        bail!("Error happens in collecting the alpha-alpha ssPT2 in output stream");
-    }
-
-    let p: Option<_> = lines
-        .iter()
-        .filter(|line| line.contains("alpha-beta"))
-        .last()
-        .map(|line| parse_os_ss(line));
-    if let Some(Some(x)) = p {
-        energy_pt2[0] = x;
-    } else {
-        bail!("Error happens in collecting the alpha-beta osPT2 in output stream");
-    }
-
-    let p: Option<_> = lines
-        .iter()
-        .filter(|line| line.contains("beta-beta"))
-        .last()
-        .map(|line| parse_os_ss(line));
-    if let Some(Some(x)) = p {
-        energy_pt2[1] += x;
-    } else {
-        bail!("Error happens in collecting the beta-beta ssPT2 in output stream");
-    }
-    comp[5] = energy_pt2[0];
-    comp[6] = energy_pt2[1];
-
-    // collect solvation energy term, which is optional
-    let p: Option<_> = lines
-        .iter()
-        .filter(|line| line.contains("Erf(P)="))
-        .last()
-        .map(|line| parse_solvent(line));
-    if let Some(Some(x)) = p {
-        energy_no_xc += x;
-    }
+// [[file:../../xo-tools.note::f493a721][f493a721]]
+//  xDH=XYG5 title
+pub(super) fn parse_requested_functional(line: &str) -> Option<Functional> {
+    let s = line.trim().strip_prefix(FUNCTIONAL_MARKER)?;
+    s.split_whitespace().next()?.parse().ok()
+}
 
-    Ok((energy_no_xc, comp))
+#[test]
+fn test_parse_requested_functional() {
+    assert_eq!(parse_requested_functional(" xDH=XYG5"), Some(Functional::XYG5));
+    let line = " xDH=XYG5 water dimer";
+    assert_eq!(parse_requested_functional(line), Some(Functional::XYG5));
+    assert_eq!(parse_requested_functional(" xDH=B3LYP"), None);
 }
-// f1ce30a8 ends here
+// f493a721 ends here
 
 // [[file:../../xo-tools.note::97608d27][97608d27]]
 impl xDH {
     /// Collect from gaussian output file or from stdin stream
     pub fn collect_from_gaussian<'a>(f: impl Into<Option<&'a Path>>) -> Result<Self> {
-        let (energy_no_xc, component) = if let Some(f) = f.into() {
+        let source = f.into();
+        let mut xdh = if let Some(f) = source {
             info!("Reading Gaussian output from {f:?} ...");
             let outfile = file_reader(f)?;
-            collect_energy_components_from(outfile)?
+            Collector::collect_from(outfile).map_err(|e| with_file_name(e, f))?
         } else {
             info!("Reading Gaussian output from stdin ...");
-            let mut stdin = std::io::stdin().lock();
-            collect_energy_components_from(stdin)?
-        };
-        let xdh = Self {
-            component,
-            energy_no_xc,
+            let stdin = stdin_reader()?;
+            Collector::collect_from(stdin)?
         };
+        xdh.source = source.map(|f| f.to_owned());
 
         Ok(xdh)
     }
+
+    /// Collect xDH results for each geometry in scans, IRCs or multi-step
+    /// jobs from gaussian output file or from stdin stream.
+    pub fn collect_all_from_gaussian<'a>(f: impl Into<Option<&'a Path>>) -> Result<Vec<Self>> {
+        let source = f.into();
+        let mut points = if let Some(f) = source {
+            info!("Reading Gaussian output from {f:?} ...");
+            let outfile = file_reader(f)?;
+            Collector::collect_all_from(outfile).map_err(|e| with_file_name(e, f))?
+        } else {
+            info!("Reading Gaussian output from stdin ...");
+            let stdin = stdin_reader()?;
+            Collector::collect_all_from(stdin)?
+        };
+        for xdh in points.iter_mut() {
+            xdh.source = source.map(|f| f.to_owned());
+        }
+        Ok(points)
+    }
 }
 // 97608d27 ends here
 
@@ -231,7 +258,8 @@ impl xDH {
 #[test]
 fn test_parse() -> Result<()> {
     let f: &Path = "tests/files/Job_o2.log".as_ref();
-    let (e_no_xc, comp) = collect_energy_components_from_file(f)?;
+    let xdh = xDH::collect_from_gaussian(f)?;
+    let (e_no_xc, comp) = (xdh.energy_no_xc, xdh.component);
     assert_eq!(e_no_xc, -133.28191752160902);
 
     #[rustfmt::skip]
@@ -240,4 +268,36 @@ fn test_parse() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_parse_snippets() -> Result<()> {
+    // relevant lines extracted from Job_o2.log
+    let f: &Path = "tests/files/xdh-lines/g09-o2.log".as_ref();
+    let xdh = xDH::collect_from_gaussian(f)?;
+    let xdh_full = xDH::collect_from_gaussian(Path::new("tests/files/Job_o2.log"))?;
+    assert_eq!(xdh.energy(Functional::XYG3), xdh_full.energy(Functional::XYG3));
+    assert_eq!(xdh.energy_reference, xdh_full.energy_reference);
+
+    // overflow in Fortran format, written as the real lines with ENTVJ
+    // field of the second line filled with asterisks
+    let s = gut::fs::read_file(f)?;
+    let s = s.replacen(
+        "ENTVJ= -133.281630 Ex=  -14.908981",
+        "ENTVJ=*********** Ex=  -14.908981",
+        1,
+    );
+    let tdir = tempfile::tempdir()?;
+    let f = tdir.path().join("overflow.log");
+    gut::fs::write_to_file(&f, &s)?;
+    let e = xDH::collect_from_gaussian(f.as_path()).unwrap_err();
+    assert!(matches!(
+        GaussianError::find(&e),
+        Some(GaussianError::ParseError { line: 10, .. })
+    ));
+    let e = e.downcast_ref::<LineError>().unwrap();
+    assert_eq!(e.line_number, 10);
+    assert_eq!(e.kind, LineErrorKind::Overflow("ENTVJ".into()));
+
+    Ok(())
+}
 // 0e2e1938 ends here
@@ -0,0 +1,270 @@
+// [[file:../../xo-tools.note::a1d93e4f][a1d93e4f]]
+use super::*;
+
+use crate::error::GaussianError;
+// a1d93e4f ends here
+
+// [[file:../../xo-tools.note::6c2b87e0][6c2b87e0]]
+/// Energy units for reporting relative energies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Hartree,
+    KcalMol,
+    KjMol,
+    EV,
+}
+
+impl Unit {
+    /// Units for reporting reaction energies
+    pub fn reaction_units() -> [Self; 3] {
+        [Self::KcalMol, Self::KjMol, Self::EV]
+    }
+
+    /// Convert `energy` in Hartree to this unit.
+    pub fn convert(&self, energy: f64) -> f64 {
+        let factor = match self {
+            Self::Hartree => 1.0,
+            Self::KcalMol => 627.5094740631,
+            Self::KjMol => 2625.4996394799,
+            Self::EV => 27.211386024367243,
+        };
+        energy * factor
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::Hartree => "Hartree",
+            Self::KcalMol => "kcal/mol",
+            Self::KjMol => "kJ/mol",
+            Self::EV => "eV",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Unit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let unit = match s.to_lowercase().as_str() {
+            "hartree" | "au" | "a.u." => Self::Hartree,
+            "kcal/mol" | "kcal" => Self::KcalMol,
+            "kj/mol" | "kj" => Self::KjMol,
+            "ev" => Self::EV,
+            _ => bail!(GaussianError::InvalidFormat(format!("unknown energy unit: {s:?}"))),
+        };
+        Ok(unit)
+    }
+}
+// 6c2b87e0 ends here
+
+// [[file:../../xo-tools.note::3e9af5c1][3e9af5c1]]
+/// A chemical reaction defined by Gaussian outputs of species and their
+/// stoichiometric coefficients, which are negative for reactants. It can be
+/// parsed from string like:
+///
+/// ```text
+/// 2*h2.log + o2.log -> 2*h2o.log
+/// -2*h2.log -1*o2.log 2*h2o.log
+/// ```
+///
+/// Species are separated by white spaces. A `+` between species must be
+/// surrounded by white spaces, as `+` is also found in file names of ions
+/// like "nh4+.log".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reaction {
+    pub species: Vec<(f64, PathBuf)>,
+}
+
+/// Split `s` into species terms separated by white spaces, with the
+/// coefficient and the path around `*` joined, like "2 * h2o.log". A
+/// standalone `+` is dropped, while `+` in path like "nh4+.log" is kept.
+fn split_species(s: &str) -> Vec<String> {
+    let mut terms: Vec<String> = vec![];
+    let mut join = false;
+    for token in s.split_whitespace() {
+        match terms.last_mut() {
+            Some(last) if join || token.starts_with('*') => last.push_str(token),
+            _ => terms.push(token.to_owned()),
+        }
+        join = token.ends_with('*');
+    }
+    terms.retain(|x| x != "+");
+    terms
+}
+
+/// Parse a species term like "2*h2o.log" or "h2o.log".
+fn parse_species(term: &str) -> Result<(f64, PathBuf)> {
+    let species = match term.split_once('*') {
+        Some((coef, path)) => {
+            let coef = coef
+                .trim()
+                .parse()
+                .map_err(|_| GaussianError::InvalidFormat(format!("invalid coefficient in {term:?}")))?;
+            (coef, path.trim().into())
+        }
+        None => (1.0, term.trim().into()),
+    };
+    Ok(species)
+}
+
+impl std::str::FromStr for Reaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let side = |x: &str, sign: f64| -> Result<Vec<_>> {
+            split_species(x)
+                .iter()
+                .map(|x| parse_species(x).map(|(coef, path)| (sign * coef, path)))
+                .collect()
+        };
+        let species = if let Some((reactants, products)) = s.split_once("->") {
+            let mut species = side(reactants, -1.0)?;
+            species.extend(side(products, 1.0)?);
+            species
+        } else {
+            side(s, 1.0)?
+        };
+        if species.is_empty() {
+            bail!(GaussianError::InvalidFormat(format!(
+                "no species found in reaction: {s:?}"
+            )));
+        }
+
+        Ok(Self { species })
+    }
+}
+
+impl Reaction {
+    /// Resolve relative paths of species against `dir`.
+    pub fn with_base_dir(mut self, dir: &Path) -> Self {
+        for (_, path) in self.species.iter_mut() {
+            *path = dir.join(&path);
+        }
+        self
+    }
+
+    /// Collect xDH results of all species. The returned linear combination
+    /// gives reaction energies for any functional.
+    pub fn collect(&self) -> Result<xDH> {
+        let mut terms = vec![];
+        for (coef, path) in &self.species {
+            let xdh = xDH::collect_from_gaussian(path.as_path())
+                .with_context(|| format!("Failed to collect xDH components from {path:?}"))?;
+            terms.push((*coef, xdh));
+        }
+        Ok(xDH::combine(&terms))
+    }
+}
+
+impl xDH {
+    /// Linear combination of xDH results, such as reaction energies from
+    /// stoichiometric coefficients and results of species. The solvation
+    /// term is kept only if available for all species.
+    pub fn combine(terms: &[(f64, xDH)]) -> Self {
+        let mut combined = Self {
+            energy_no_xc: 0.0,
+            component: [0.0; N],
+            energy_reference: 0.0,
+            energy_solvation: Some(0.0),
+            functional: None,
+            source: None,
+            spin: None,
+            geometry: None,
+        };
+        for (coef, xdh) in terms {
+            combined.energy_no_xc += coef * xdh.energy_no_xc;
+            combined.energy_reference += coef * xdh.energy_reference;
+            for (x, c) in combined.component.iter_mut().zip(xdh.component) {
+                *x += coef * c;
+            }
+            combined.energy_solvation = combined
+                .energy_solvation
+                .zip(xdh.energy_solvation)
+                .map(|(x, e)| x + coef * e);
+        }
+        combined
+    }
+
+    /// Format relative energies for B3LYP reference, all builtin xDH
+    /// functionals and custom `functionals` in kcal/mol, kJ/mol and eV.
+    pub fn report_relative_energies(&self, functionals: &[CustomFunctional]) -> String {
+        let mut rows = vec![("B3LYP".to_owned(), self.energy_reference)];
+        rows.extend(self.energies().into_iter().map(|(f, e)| (f.to_string(), e)));
+        for f in functionals {
+            if !rows.iter().any(|(x, _)| x == &f.name) {
+                rows.push((f.name.clone(), self.energy_with(&f.parameters)));
+            }
+        }
+
+        let units = Unit::reaction_units();
+        let mut txt = format!("  {:<12}", "functional");
+        for unit in units {
+            txt.push_str(&format!(" {:>14}", unit.to_string()));
+        }
+        txt.push('\n');
+        for (name, energy) in rows {
+            txt.push_str(&format!("  {name:<12}"));
+            for unit in units {
+                txt.push_str(&format!(" {:>14.4}", unit.convert(energy)));
+            }
+            txt.push('\n');
+        }
+        txt
+    }
+}
+// 3e9af5c1 ends here
+
+// [[file:../../xo-tools.note::9b07d2ce][9b07d2ce]]
+#[test]
+fn test_xdh_reaction() -> Result<()> {
+    let r: Reaction = "2*a.log + b.log -> 2 * c.log".parse()?;
+    assert_eq!(
+        r.species,
+        [(-2.0, "a.log".into()), (-1.0, "b.log".into()), (2.0, "c.log".into())]
+    );
+    let r2: Reaction = "-2*a.log -1*b.log 2*c.log".parse()?;
+    assert_eq!(r, r2);
+    let r2: Reaction = "-2 * a.log -1 *b.log 2* c.log".parse()?;
+    assert_eq!(r, r2);
+    // ions with "+" in file names
+    let r: Reaction = "nh3.log + h+.log -> nh4+.log".parse()?;
+    assert_eq!(
+        r.species,
+        [
+            (-1.0, "nh3.log".into()),
+            (-1.0, "h+.log".into()),
+            (1.0, "nh4+.log".into())
+        ]
+    );
+    let r2: Reaction = "-1*nh3.log -1*h+.log nh4+.log".parse()?;
+    assert_eq!(r, r2);
+    let e = "x*a.log -> b.log".parse::<Reaction>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+    let e = " -> ".parse::<Reaction>().unwrap_err();
+    assert_eq!(crate::error::exit_code(&e), 4);
+
+    for unit in [Unit::Hartree, Unit::KcalMol, Unit::KjMol, Unit::EV] {
+        assert_eq!(unit.to_string().parse::<Unit>()?, unit);
+    }
+    assert_eq!("KCAL".parse::<Unit>()?, Unit::KcalMol);
+    assert!("cm-1".parse::<Unit>().is_err());
+
+    let r: Reaction = "2*Job_o2.log -> Job_o2.log".parse()?;
+    let r = r.with_base_dir("tests/files".as_ref());
+    let xdh = r.collect()?;
+    let o2 = xDH::collect_from_gaussian(Path::new("tests/files/Job_o2.log"))?;
+    assert!((xdh.energy(Functional::XYG3) + o2.energy(Functional::XYG3)).abs() < 1e-8);
+    assert!((xdh.energy_reference + o2.energy_reference).abs() < 1e-8);
+    assert!((xdh.energy_solvation.unwrap() + o2.energy_solvation.unwrap()).abs() < 1e-12);
+
+    assert_eq!(Unit::KcalMol.convert(1.0), 627.5094740631);
+    let txt = xdh.report_relative_energies(&[]);
+    assert_eq!(txt.lines().count(), 1 + 1 + 6);
+    assert!(txt.lines().next().unwrap().contains("kcal/mol"));
+
+    Ok(())
+}
+// 9b07d2ce ends here
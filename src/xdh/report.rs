@@ -0,0 +1,338 @@
+// [[file:../../xo-tools.note::e6b3bd2f][e6b3bd2f]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+// e6b3bd2f ends here
+
+// [[file:../../xo-tools.note::c548d8cc][c548d8cc]]
+impl xDH {
+    /// Return energies of all functionals in the xDH family.
+    pub fn energies(&self) -> Vec<(Functional, f64)> {
+        Functional::all().into_iter().map(|f| (f, self.energy(f))).collect()
+    }
+
+    /// Return the named energy components, including the energy without
+    /// exchange-correlation contribution and the optional solvation term.
+    pub fn named_components(&self) -> Vec<(&'static str, f64)> {
+        let solvation = self.energy_solvation.unwrap_or(0.0);
+        let mut components = vec![("E(noXC)", self.energy_no_xc - solvation)];
+        components.extend(COMPONENT_NAMES.into_iter().zip(self.component));
+        if let Some(x) = self.energy_solvation {
+            components.push(("E(solv)", x));
+        }
+        components
+    }
+
+    /// Return labelled energies of `functionals` to be reported. If `all`
+    /// is true, the B3LYP reference energy, energies of all builtin xDH
+    /// functionals and energy components are included as well.
+    pub fn report_rows(&self, functionals: &[CustomFunctional], all: bool) -> Vec<(String, f64)> {
+        let mut rows = vec![];
+        if all {
+            rows.push(("E(B3LYP)".to_owned(), self.energy_reference));
+            for (f, energy) in self.energies() {
+                rows.push((format!("E({f})"), energy));
+            }
+        }
+        for f in functionals {
+            let name = format!("E({})", f.name);
+            if !rows.iter().any(|(x, _)| x == &name) {
+                rows.push((name, self.energy_with(&f.parameters)));
+            }
+        }
+        if all {
+            for (name, energy) in self.named_components() {
+                rows.push((name.to_owned(), energy));
+            }
+        }
+        rows
+    }
+
+    /// Format energies in human-readable text. See also `report_rows`.
+    pub fn report_text(&self, functionals: &[CustomFunctional], all: bool) -> String {
+        self.report_rows(functionals, all)
+            .into_iter()
+            .map(|(name, energy)| format!("  {name:<10} =  {energy:16.8} A.U.\n"))
+            .collect()
+    }
+
+    /// Format energies in CSV for scripting. See also `report_rows`.
+    pub fn report_csv(&self, functionals: &[CustomFunctional], all: bool) -> String {
+        let mut csv = String::from("name,energy\n");
+        for (name, energy) in self.report_rows(functionals, all) {
+            csv.push_str(&format!("{name},{energy}\n"));
+        }
+        csv
+    }
+}
+// c548d8cc ends here
+
+// [[file:../../xo-tools.note::3f1c9e27][3f1c9e27]]
+/// The version of `Report` schema, which will be bumped only on breaking
+/// changes.
+const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// The seven energy components in xDH calculation, in Hartree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedComponents {
+    pub ex_hf: f64,
+    pub ex_s: f64,
+    pub ex_b: f64,
+    pub ec_vwn: f64,
+    pub ec_lyp: f64,
+    pub e2_os: f64,
+    pub e2_ss: f64,
+}
+
+impl From<Component> for NamedComponents {
+    fn from(c: Component) -> Self {
+        let [ex_hf, ex_s, ex_b, ec_vwn, ec_lyp, e2_os, e2_ss] = c;
+        Self {
+            ex_hf,
+            ex_s,
+            ex_b,
+            ec_vwn,
+            ec_lyp,
+            e2_os,
+            e2_ss,
+        }
+    }
+}
+
+/// Serializable xDH results with a stable schema for external scripts.
+///
+/// The energy of any functional equals to `energy_no_xc` plus the sum of
+/// parameter weighted `components` plus `energy_solvation` if available.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    /// The Gaussian output file, absent if read from stdin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// The unit of all energies
+    pub unit: String,
+    /// The reported functional
+    pub functional: String,
+    /// The energy of the reported functional
+    pub energy: f64,
+    /// The SCF energy of B3LYP reference
+    pub energy_reference: f64,
+    /// The energy without exchange-correlation contribution and solvation term
+    pub energy_no_xc: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_solvation: Option<f64>,
+    pub components: NamedComponents,
+    /// Energies of all builtin xDH functionals and custom ones
+    pub energies: BTreeMap<String, f64>,
+    /// Spin diagnostics for open-shell reference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spin: Option<SpinDiagnostics>,
+    /// The geometry for which results are calculated, in Angstrom
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<Geometry>,
+}
+
+/// Serializable results at every geometry in a relaxed scan, IRC or
+/// multi-step job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub schema_version: u32,
+    pub points: Vec<Report>,
+}
+
+impl xDH {
+    /// Return serializable results for the `selected` functional. Energies
+    /// of all builtin xDH functionals, `selected` and `extra` functionals
+    /// are included.
+    pub fn report(&self, selected: &CustomFunctional, extra: &[CustomFunctional]) -> Report {
+        let mut energies: BTreeMap<_, _> = self.energies().into_iter().map(|(f, e)| (f.to_string(), e)).collect();
+        for f in std::iter::once(selected).chain(extra) {
+            energies.insert(f.name.clone(), self.energy_with(&f.parameters));
+        }
+
+        Report {
+            schema_version: REPORT_SCHEMA_VERSION,
+            source: self.source.clone(),
+            unit: Unit::Hartree.to_string(),
+            functional: selected.name.clone(),
+            energy: self.energy_with(&selected.parameters),
+            energy_reference: self.energy_reference,
+            energy_no_xc: self.energy_no_xc - self.energy_solvation.unwrap_or(0.0),
+            energy_solvation: self.energy_solvation,
+            components: self.component.into(),
+            energies,
+            spin: self.spin,
+            geometry: self.geometry.clone(),
+        }
+    }
+
+    /// Return serializable results of all `points` for the `selected`
+    /// functional. See also `report`.
+    pub fn report_scan(points: &[Self], selected: &CustomFunctional, extra: &[CustomFunctional]) -> ScanReport {
+        ScanReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            points: points.iter().map(|x| x.report(selected, extra)).collect(),
+        }
+    }
+
+    /// Return rows of B3LYP reference energy and the energy of `functional`
+    /// at each of `points`, with energies relative to the first point in
+    /// kcal/mol.
+    fn scan_rows(points: &[Self], functional: &CustomFunctional) -> Vec<[f64; 4]> {
+        let energies: Vec<_> = points
+            .iter()
+            .map(|x| (x.energy_reference, x.energy_with(&functional.parameters)))
+            .collect();
+        let Some(&(ref0, e0)) = energies.first() else {
+            return vec![];
+        };
+        let unit = Unit::KcalMol;
+        energies
+            .into_iter()
+            .map(|(r, e)| [r, e, unit.convert(r - ref0), unit.convert(e - e0)])
+            .collect()
+    }
+
+    /// Format energies at each of `points` in a human-readable table for
+    /// plotting potential energy surface.
+    pub fn report_scan_text(points: &[Self], functional: &CustomFunctional) -> String {
+        let name = format!("E({})", functional.name);
+        let mut txt = format!(
+            "  {:>5} {:>16} {:>16} {:>12} {:>12}\n",
+            "point",
+            "E(B3LYP)",
+            name,
+            "dE(B3LYP)",
+            format!("d{name}")
+        );
+        for (i, [r, e, dr, de]) in Self::scan_rows(points, functional).into_iter().enumerate() {
+            txt.push_str(&format!("  {:>5} {r:16.8} {e:16.8} {dr:12.4} {de:12.4}\n", i + 1));
+        }
+        txt.push_str(&format!("  (relative energies in {})\n", Unit::KcalMol));
+        txt
+    }
+
+    /// Format energies at each of `points` in CSV. See also `report_scan_text`.
+    pub fn report_scan_csv(points: &[Self], functional: &CustomFunctional) -> String {
+        let mut csv = format!(
+            "point,E(B3LYP),E({0}),dE(B3LYP)/kcal_mol,dE({0})/kcal_mol\n",
+            functional.name
+        );
+        for (i, [r, e, dr, de]) in Self::scan_rows(points, functional).into_iter().enumerate() {
+            csv.push_str(&format!("{},{r},{e},{dr},{de}\n", i + 1));
+        }
+        csv
+    }
+}
+
+impl ScanReport {
+    /// Format as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Format as TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+impl Report {
+    /// Format as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Format as TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+// 3f1c9e27 ends here
+
+// [[file:../../xo-tools.note::594513f9][594513f9]]
+#[test]
+fn test_xdh_report() -> Result<()> {
+    let f: &Path = "tests/files/Job_o2.log".as_ref();
+    let xdh = xDH::collect_from_gaussian(f)?;
+
+    let xyg5 = [Functional::XYG5.into()];
+    let rows = xdh.report_rows(&xyg5, false);
+    assert_eq!(rows, [("E(XYG5)".to_owned(), xdh.energy(Functional::XYG5))]);
+
+    let rows = xdh.report_rows(&xyg5, true);
+    // B3LYP, 6 functionals, E(noXC), 7 components and solvation term
+    assert_eq!(rows.len(), 1 + 6 + 1 + 7 + 1);
+    assert_eq!(rows[0].0, "E(B3LYP)");
+    assert_eq!(rows.last().unwrap().0, "E(solv)");
+    let e_no_xc: f64 = rows[7].1 + rows[15].1;
+    assert!((e_no_xc - -133.28191752160902).abs() < 1e-10);
+
+    let txt = xdh.report_text(&xyg5, true);
+    assert!(txt.contains("  E(XYGJ-OS) =     -149.96275308 A.U.\n"));
+    let csv = xdh.report_csv(&xyg5, true);
+    assert!(csv.starts_with("name,energy\nE(B3LYP),-150.367567881\n"));
+
+    // custom functionals are placed after builtin ones
+    let custom = CustomFunctional::from_csv("custom", "1, 0, 0, 0, 0, 0, 0")?;
+    let rows = xdh.report_rows(&[custom], true);
+    assert_eq!(rows[7].0, "E(custom)");
+
+    Ok(())
+}
+
+#[test]
+fn test_xdh_report_serde() -> Result<()> {
+    let f: &Path = "tests/files/Job_o2.log".as_ref();
+    let xdh = xDH::collect_from_gaussian(f)?;
+
+    let custom = CustomFunctional::from_csv("custom", "1, 0, 0, 0, 0, 0, 0")?;
+    let report = xdh.report(&Functional::XYG5.into(), &[custom]);
+    assert_eq!(report.functional, "XYG5");
+    assert_eq!(report.energy, xdh.energy(Functional::XYG5));
+    assert_eq!(report.energies.len(), 6 + 1);
+    assert_eq!(report.energies["XYGJ-OS"], xdh.energy(Functional::XYGJ_OS));
+    assert_eq!(report.source.as_deref(), Some(f));
+    let solvation = report.energy_solvation.unwrap();
+    assert!((report.energy_no_xc + solvation - -133.28191752160902).abs() < 1e-10);
+
+    let json = report.to_json()?;
+    assert!(json.contains(r#""unit": "Hartree""#));
+    assert!(json.contains(r#""ex_hf": "#));
+    assert_eq!(serde_json::from_str::<Report>(&json)?, report);
+    let toml = report.to_toml()?;
+    assert!(toml.contains("[components]"));
+    assert!(toml.contains("[spin]"));
+    assert_eq!(toml::from_str::<Report>(&toml)?, report);
+
+    Ok(())
+}
+
+#[test]
+fn test_xdh_report_scan() -> Result<()> {
+    let f: &Path = "tests/files/xdh-scan.log".as_ref();
+    let points = xDH::collect_all_from_gaussian(f)?;
+    let xyg3: CustomFunctional = Functional::XYG3.into();
+
+    let txt = xDH::report_scan_text(&points, &xyg3);
+    assert_eq!(txt.lines().count(), 1 + 2 + 1);
+    assert!(txt.contains("dE(XYG3)"));
+    let csv = xDH::report_scan_csv(&points, &xyg3);
+    let last = csv.lines().last().unwrap();
+    let de: f64 = last.rsplit(',').next().unwrap().parse()?;
+    let de_expected = Unit::KcalMol.convert(points[1].energy(Functional::XYG3) - points[0].energy(Functional::XYG3));
+    assert!((de - de_expected).abs() < 1e-8);
+
+    let report = xDH::report_scan(&points, &xyg3, &[]);
+    assert_eq!(report.points.len(), 2);
+    assert_eq!(report.points[1].geometry.as_ref(), points[1].geometry());
+    let toml = report.to_toml()?;
+    assert!(toml.contains("[[points]]"));
+    assert_eq!(toml::from_str::<ScanReport>(&toml)?, report);
+    let json = report.to_json()?;
+    assert_eq!(serde_json::from_str::<ScanReport>(&json)?, report);
+
+    Ok(())
+}
+// 594513f9 ends here
@@ -0,0 +1,108 @@
+// [[file:../../xo-tools.note::7a4e1c02][7a4e1c02]]
+use super::*;
+
+use serde::{Deserialize, Serialize};
+// 7a4e1c02 ends here
+
+// [[file:../../xo-tools.note::d58c3b9e][d58c3b9e]]
+/// Spin diagnostics of an open-shell (unrestricted) reference calculation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpinDiagnostics {
+    pub multiplicity: usize,
+    pub alpha_electrons: usize,
+    pub beta_electrons: usize,
+    /// <S**2> before annihilation of the first spin contaminant
+    pub s2_before: f64,
+    /// <S**2> after annihilation of the first spin contaminant
+    pub s2_after: f64,
+}
+
+impl SpinDiagnostics {
+    /// The exact <S**2> value of S(S+1) for the multiplicity
+    pub fn s2_expected(&self) -> f64 {
+        let s = (self.multiplicity as f64 - 1.0) / 2.0;
+        s * (s + 1.0)
+    }
+
+    /// The deviation of <S**2> before annihilation from the exact value
+    pub fn contamination(&self) -> f64 {
+        self.s2_before - self.s2_expected()
+    }
+}
+
+//  Charge =  0 Multiplicity = 3
+pub(super) fn parse_multiplicity(line: &str) -> Option<usize> {
+    line.split_once("Multiplicity =")?
+        .1
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+//      9 alpha electrons        7 beta electrons
+pub(super) fn parse_electrons(line: &str) -> Option<(usize, usize)> {
+    let parts: Vec<_> = line.split_whitespace().collect();
+    match parts[..] {
+        [na, "alpha", "electrons", nb, "beta", "electrons"] => Some((na.parse().ok()?, nb.parse().ok()?)),
+        _ => None,
+    }
+}
+
+//  S**2 before annihilation     2.0080,   after     2.0000
+pub(super) fn parse_annihilation(line: &str) -> Option<(f64, f64)> {
+    let (before, after) = line.trim().strip_prefix("S**2 before annihilation")?.split_once(", ")?;
+    let after = after.trim().strip_prefix("after")?;
+    Some((before.trim().parse().ok()?, after.trim().parse().ok()?))
+}
+
+impl xDH {
+    /// Return spin diagnostics for open-shell reference calculation.
+    pub fn spin_diagnostics(&self) -> Option<&SpinDiagnostics> {
+        self.spin.as_ref()
+    }
+
+    /// Return a warning message if the deviation of <S**2> from the exact
+    /// value exceeds `threshold`, for which the xDH energy is untrustworthy.
+    pub fn check_spin_contamination(&self, threshold: f64) -> Option<String> {
+        let spin = self.spin.as_ref()?;
+        let ds2 = spin.contamination();
+        if ds2.abs() > threshold {
+            let msg = format!(
+                "spin contamination in B3LYP reference: <S**2> = {:.4} (expected {:.4}, deviation {ds2:.4} > {threshold})",
+                spin.s2_before,
+                spin.s2_expected()
+            );
+            Some(msg)
+        } else {
+            None
+        }
+    }
+}
+// d58c3b9e ends here
+
+// [[file:../../xo-tools.note::1f6a09b3][1f6a09b3]]
+#[test]
+fn test_spin_diagnostics() -> Result<()> {
+    assert_eq!(parse_multiplicity(" Charge =  0 Multiplicity = 3"), Some(3));
+    assert_eq!(
+        parse_electrons("     9 alpha electrons        7 beta electrons"),
+        Some((9, 7))
+    );
+    assert_eq!(parse_electrons(" There are 9 alpha electrons"), None);
+    let line = " S**2 before annihilation     2.0080,   after     2.0000";
+    assert_eq!(parse_annihilation(line), Some((2.008, 2.0)));
+
+    let f: &Path = "tests/files/Job_o2.log".as_ref();
+    let xdh = xDH::collect_from_gaussian(f)?;
+    let spin = xdh.spin_diagnostics().unwrap();
+    assert_eq!(spin.multiplicity, 3);
+    assert_eq!((spin.alpha_electrons, spin.beta_electrons), (9, 7));
+    assert_eq!(spin.s2_expected(), 2.0);
+    assert!((spin.contamination() - 0.008).abs() < 1e-10);
+    assert!(xdh.check_spin_contamination(0.1).is_none());
+    assert!(xdh.check_spin_contamination(0.005).is_some());
+
+    Ok(())
+}
+// 1f6a09b3 ends here
@@ -0,0 +1,187 @@
+// [[file:../../xo-tools.note::5c3e8f17][5c3e8f17]]
+use super::*;
+
+use crate::input::{GaussianInput, Keyword, Route};
+// 5c3e8f17 ends here
+
+// [[file:../../xo-tools.note::b47d2e90][b47d2e90]]
+/// Gaussian job types relevant for xDH calculation, which provides energy
+/// only without analytic gradients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobType {
+    SinglePoint,
+    Optimization,
+    Frequency,
+    Force,
+    /// TD-DFT or other excited states calculation
+    ExcitedStates,
+    Irc,
+    Oniom,
+    /// Polarizability by `Polar`
+    Polarizability,
+    Nmr,
+    /// Reoptimize wavefunction for instability by `Stable=Opt`
+    StabilityOptimization,
+}
+
+impl JobType {
+    /// Return the job type requested by route keyword `kw`.
+    fn from_keyword(kw: &Keyword) -> Option<Self> {
+        let job = match kw.name.to_lowercase().as_str() {
+            "sp" => Self::SinglePoint,
+            "opt" | "optimization" => Self::Optimization,
+            "freq" | "frequency" => Self::Frequency,
+            "force" => Self::Force,
+            "td" | "tda" | "cis" => Self::ExcitedStates,
+            "irc" => Self::Irc,
+            "oniom" => Self::Oniom,
+            "polar" => Self::Polarizability,
+            "nmr" => Self::Nmr,
+            // stability analysis only is fine for the reference wavefunction
+            "stable" if kw.options.iter().any(|x| x.eq_ignore_ascii_case("opt")) => Self::StabilityOptimization,
+            _ => return None,
+        };
+        Some(job)
+    }
+
+    /// Classify job types requested in `route`. Single point is assumed if
+    /// no job type keyword found.
+    pub fn classify(route: &Route) -> Vec<Self> {
+        let jobs: Vec<_> = route.keywords().filter_map(Self::from_keyword).collect();
+        if jobs.is_empty() {
+            vec![Self::SinglePoint]
+        } else {
+            jobs
+        }
+    }
+}
+
+impl std::fmt::Display for JobType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::SinglePoint => "single point",
+            Self::Optimization => "geometry optimization",
+            Self::Frequency => "frequency",
+            Self::Force => "force",
+            Self::ExcitedStates => "excited states",
+            Self::Irc => "IRC",
+            Self::Oniom => "ONIOM",
+            Self::Polarizability => "polarizability",
+            Self::Nmr => "NMR",
+            Self::StabilityOptimization => "wavefunction stability optimization",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Return true if xDH functional is requested in ONIOM layers, such as
+/// `ONIOM(XYG3/6-31G(d):UFF)`.
+pub(super) fn requests_xdh_in_oniom(route: &Route) -> bool {
+    let Some(oniom) = route.find_keyword("oniom") else {
+        return false;
+    };
+    oniom
+        .options
+        .iter()
+        .flat_map(|x| x.split([':', '/']))
+        .filter_map(|x| x.split('(').next())
+        .any(|name| name.parse::<Functional>().is_ok())
+}
+
+/// Return true if any atom in molecule specification has ONIOM layer
+/// label, such as "N 0 0.0 0.0 0.1 H" or "H 0 0.0 0.9 -0.2 L H 3".
+fn has_oniom_layers(input: &GaussianInput) -> bool {
+    input.molecule_spec().iter().any(|line| {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        parts.windows(4).any(|w| {
+            w[..3].iter().all(|x| x.parse::<f64>().is_ok()) && matches!(w[3].to_uppercase().as_str(), "H" | "M" | "L")
+        })
+    })
+}
+
+/// Validate job step `input` requesting xDH functional against incompatible
+/// job types. Geometry optimization is turned into single point calculation
+/// at input geometry, and jobs requiring gradients, response properties or
+/// excited states are refused.
+pub(super) fn validate_job_step(input: &mut GaussianInput) -> Result<()> {
+    let mut route = input.parse_route()?;
+    for job in JobType::classify(&route) {
+        match job {
+            JobType::SinglePoint => {}
+            JobType::Optimization => {
+                let kw = route
+                    .remove_keyword("opt")
+                    .or_else(|| route.remove_keyword("optimization"));
+                warn!(
+                    "{job} ({}) is not supported without analytic gradients of xDH, turned into single point calculation at input geometry.",
+                    kw.map(|kw| kw.to_string()).unwrap_or_default()
+                );
+                input.route.lines = vec![route.to_string()];
+            }
+            JobType::Frequency | JobType::Force | JobType::Irc => {
+                bail!("{job} calculation requires analytic derivatives, which are not available for xDH functionals.")
+            }
+            JobType::Polarizability | JobType::Nmr | JobType::StabilityOptimization => {
+                bail!("{job} calculation requires response properties, which are not available for xDH functionals.")
+            }
+            JobType::ExcitedStates => bail!("{job} calculation is not supported for xDH functionals."),
+            JobType::Oniom => bail!("{job} calculation with xDH functional in layers is not supported."),
+        }
+    }
+    if has_oniom_layers(input) {
+        warn!("ONIOM layers found in molecule specification are ignored: the whole system will be treated with xDH functional.");
+    }
+    Ok(())
+}
+// b47d2e90 ends here
+
+// [[file:../../xo-tools.note::0f8d6a35][0f8d6a35]]
+#[test]
+fn test_validate_job_step() -> Result<()> {
+    let route: Route = "# XYG3/6-31g opt=(calcfc,tight) freq".parse()?;
+    assert_eq!(JobType::classify(&route), [JobType::Optimization, JobType::Frequency]);
+    let route: Route = "# XYG3/6-31g scf=tight".parse()?;
+    assert_eq!(JobType::classify(&route), [JobType::SinglePoint]);
+    let route: Route = "# XYG3/6-31g stable".parse()?;
+    assert_eq!(JobType::classify(&route), [JobType::SinglePoint]);
+    let route: Route = "# XYG3/6-31g Stable=Opt polar".parse()?;
+    assert_eq!(
+        JobType::classify(&route),
+        [JobType::StabilityOptimization, JobType::Polarizability]
+    );
+    let route: Route = "# oniom(xyg3/6-31g(d):uff)".parse()?;
+    assert_eq!(JobType::classify(&route), [JobType::Oniom]);
+    assert!(requests_xdh_in_oniom(&route));
+    let route: Route = "# oniom(b3lyp/6-31g(d):uff)".parse()?;
+    assert!(!requests_xdh_in_oniom(&route));
+
+    // optimization is turned into single point calculation
+    let mut input: GaussianInput =
+        "# XYG3/6-31g Opt=Tight scf=tight\n\ntitle\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n".parse()?;
+    validate_job_step(&mut input)?;
+    assert_eq!(input.route.lines, ["# XYG3/6-31g scf=tight"]);
+
+    for route in [
+        "# XYG3/6-31g freq",
+        "# td xyg3/6-31g",
+        "# XYG3/6-31g irc",
+        "# oniom(xyg3/6-31g:uff)",
+        "# XYG3/6-31g polar",
+        "# XYG3/6-31g NMR=GIAO",
+        "# XYG3/6-31g stable=opt",
+        "# XYG3/6-31g stable=(opt,xqc)",
+    ] {
+        let mut input: GaussianInput = format!("{route}\n\ntitle\n\n0 1\nH 0 0 0\nH 0 0 0.74\n\n").parse()?;
+        assert!(validate_job_step(&mut input).is_err(), "{route}");
+    }
+
+    // ONIOM layers without ONIOM keyword
+    let mut input = GaussianInput::from_file("tests/files/Test009.gjf")?;
+    assert!(has_oniom_layers(&input));
+    validate_job_step(&mut input)?;
+    let input = GaussianInput::from_file("tests/files/Test005.gjf")?;
+    assert!(!has_oniom_layers(&input));
+
+    Ok(())
+}
+// 0f8d6a35 ends here